@@ -0,0 +1,95 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! A message wrapper that remembers its serialized form, for code that
+//! resends a message only when it has changed.
+
+/// Owns a message together with the bytes it last serialized to.
+///
+/// The bytes are dropped whenever the message is borrowed mutably, so
+/// `serialize` only does work after the message may have changed.
+#[derive(Debug)]
+pub struct CachedMessage<M> {
+    msg: M,
+    bytes: Option<Vec<u8>>,
+}
+
+impl<M> CachedMessage<M> {
+    pub fn new(msg: M) -> Self {
+        Self { msg, bytes: None }
+    }
+
+    pub fn get(&self) -> &M {
+        &self.msg
+    }
+
+    /// Returns the message for mutation, invalidating the cached bytes.
+    pub fn get_mut(&mut self) -> &mut M {
+        self.bytes = None;
+        &mut self.msg
+    }
+
+    /// Returns whether `serialize` would return the cached bytes.
+    pub fn is_cached(&self) -> bool {
+        self.bytes.is_some()
+    }
+
+    pub fn into_inner(self) -> M {
+        self.msg
+    }
+}
+
+impl<M> CachedMessage<M>
+where
+    for<'a> &'a M: Into<Vec<u8>>,
+{
+    /// Returns the serialized message, serializing it only if it was
+    /// borrowed mutably since the last call.
+    pub fn serialize(&mut self) -> &[u8] {
+        let msg = &self.msg;
+        self.bytes.get_or_insert_with(|| msg.into())
+    }
+}
+
+impl<M> From<M> for CachedMessage<M> {
+    fn from(msg: M) -> Self {
+        Self::new(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct FakeMessage {
+        value: u8,
+        serializations: Cell<usize>,
+    }
+
+    impl From<&FakeMessage> for Vec<u8> {
+        fn from(msg: &FakeMessage) -> Self {
+            msg.serializations.set(msg.serializations.get() + 1);
+            vec![msg.value]
+        }
+    }
+
+    #[test]
+    fn serialize_caches_until_mutated() {
+        let mut cached = CachedMessage::new(FakeMessage { value: 1, serializations: Cell::new(0) });
+        assert!(!cached.is_cached());
+        assert_eq!(cached.serialize(), [1]);
+        assert_eq!(cached.serialize(), [1]);
+        assert_eq!(cached.get().serializations.get(), 1);
+
+        cached.get_mut().value = 2;
+        assert!(!cached.is_cached());
+        assert_eq!(cached.serialize(), [2]);
+        assert_eq!(cached.get().serializations.get(), 2);
+        assert!(cached.is_cached());
+    }
+}
@@ -11,11 +11,13 @@ use crate::__internal::{Private, RawArena, RawMessage, RawRepeatedField};
 use paste::paste;
 use std::alloc::Layout;
 use std::cell::UnsafeCell;
+use std::cmp::Ordering;
 use std::fmt;
 use std::marker::PhantomData;
-use std::mem::MaybeUninit;
-use std::ops::Deref;
+use std::mem::{self, MaybeUninit};
+use std::ops::{Deref, Range};
 use std::ptr::{self, NonNull};
+use std::slice;
 
 /// A wrapper over a `proto2::Arena`.
 ///
@@ -110,6 +112,36 @@ impl SerializedData {
     fn as_mut_ptr(&mut self) -> *mut [u8] {
         ptr::slice_from_raw_parts_mut(self.data.as_ptr(), self.len)
     }
+
+    /// Returns the bytes in `range`, or `None` if it is out of bounds.
+    pub fn slice(&self, range: std::ops::Range<usize>) -> Option<&[u8]> {
+        self.get(range)
+    }
+
+    /// Concatenates the bytes of `parts` into one buffer, e.g. to build a
+    /// stream of back-to-back messages.
+    pub fn concat(parts: &[SerializedData]) -> Self {
+        let mut bytes = Vec::with_capacity(parts.iter().map(|part| part.len()).sum());
+        for part in parts {
+            bytes.extend_from_slice(part);
+        }
+        let bytes = bytes.into_boxed_slice();
+        let len = bytes.len();
+        let data = NonNull::new(Box::into_raw(bytes).cast::<u8>()).unwrap();
+        // SAFETY: `data` was allocated by the Rust global allocator as a
+        // `Box<[u8]>` of `len` bytes, and ownership was released above.
+        unsafe { Self::from_raw_parts(data, len) }
+    }
+
+    /// Converts into a `Vec<u8>` without copying the serialized bytes.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        // SAFETY: `data` was allocated by the Rust global allocator with a
+        // size of `len` and align of 1 as promised by `from_raw_parts`.
+        let data = unsafe { Box::from_raw(self.as_mut_ptr()) };
+        // Ownership moved to `data`, so `self` must not free it again.
+        mem::forget(self);
+        data.into_vec()
+    }
 }
 
 impl Deref for SerializedData {
@@ -184,6 +216,16 @@ pub fn copy_bytes_in_arena_if_needed_by_runtime<'a>(
     val
 }
 
+/// Concatenates `head` and `tail`. The setter copies the result into the
+/// message's own string, so it only needs to live until then.
+pub fn concat_bytes_in_arena_if_needed_by_runtime(
+    _msg_ref: MutatorMessageRef<'_>,
+    head: &[u8],
+    tail: &[u8],
+) -> Vec<u8> {
+    [head, tail].concat()
+}
+
 /// RepeatedField impls delegate out to `extern "C"` functions exposed by
 /// `cpp_api.h` and store either a RepeatedField* or a RepeatedPtrField*
 /// depending on the type.
@@ -230,11 +272,19 @@ impl<'msg, T: ?Sized> Clone for RepeatedField<'msg, T> {
 
 pub trait RepeatedScalarOps {
     fn new_repeated_field() -> RawRepeatedField;
+    fn free_repeated_field(f: RawRepeatedField);
     fn push(f: RawRepeatedField, v: Self);
     fn len(f: RawRepeatedField) -> usize;
     fn get(f: RawRepeatedField, i: usize) -> Self;
     fn set(f: RawRepeatedField, i: usize, v: Self);
     fn copy_from(src: RawRepeatedField, dst: RawRepeatedField);
+    fn data(f: RawRepeatedField) -> *const Self;
+    fn mutable_data(f: RawRepeatedField) -> *mut Self;
+    fn capacity(f: RawRepeatedField) -> usize;
+    fn reserve(f: RawRepeatedField, size: usize);
+    fn shrink_to_fit(f: RawRepeatedField);
+    fn clear(f: RawRepeatedField);
+    fn truncate(f: RawRepeatedField, len: usize);
 }
 
 macro_rules! impl_repeated_scalar_ops {
@@ -242,16 +292,27 @@ macro_rules! impl_repeated_scalar_ops {
         paste! { $(
             extern "C" {
                 fn [< __pb_rust_RepeatedField_ $t _new >]() -> RawRepeatedField;
+                fn [< __pb_rust_RepeatedField_ $t _free >](f: RawRepeatedField);
                 fn [< __pb_rust_RepeatedField_ $t _add >](f: RawRepeatedField, v: $t);
                 fn [< __pb_rust_RepeatedField_ $t _size >](f: RawRepeatedField) -> usize;
                 fn [< __pb_rust_RepeatedField_ $t _get >](f: RawRepeatedField, i: usize) -> $t;
                 fn [< __pb_rust_RepeatedField_ $t _set >](f: RawRepeatedField, i: usize, v: $t);
                 fn [< __pb_rust_RepeatedField_ $t _copy_from >](src: RawRepeatedField, dst: RawRepeatedField);
+                fn [< __pb_rust_RepeatedField_ $t _data >](f: RawRepeatedField) -> *const $t;
+                fn [< __pb_rust_RepeatedField_ $t _mutable_data >](f: RawRepeatedField) -> *mut $t;
+                fn [< __pb_rust_RepeatedField_ $t _capacity >](f: RawRepeatedField) -> usize;
+                fn [< __pb_rust_RepeatedField_ $t _reserve >](f: RawRepeatedField, size: usize);
+                fn [< __pb_rust_RepeatedField_ $t _shrink_to_fit >](f: RawRepeatedField);
+                fn [< __pb_rust_RepeatedField_ $t _clear >](f: RawRepeatedField);
+                fn [< __pb_rust_RepeatedField_ $t _truncate >](f: RawRepeatedField, len: usize);
             }
             impl RepeatedScalarOps for $t {
                 fn new_repeated_field() -> RawRepeatedField {
                     unsafe { [< __pb_rust_RepeatedField_ $t _new >]() }
                 }
+                fn free_repeated_field(f: RawRepeatedField) {
+                    unsafe { [< __pb_rust_RepeatedField_ $t _free >](f) }
+                }
                 fn push(f: RawRepeatedField, v: Self) {
                     unsafe { [< __pb_rust_RepeatedField_ $t _add >](f, v) }
                 }
@@ -267,6 +328,27 @@ macro_rules! impl_repeated_scalar_ops {
                 fn copy_from(src: RawRepeatedField, dst: RawRepeatedField) {
                     unsafe { [< __pb_rust_RepeatedField_ $t _copy_from >](src, dst) }
                 }
+                fn data(f: RawRepeatedField) -> *const Self {
+                    unsafe { [< __pb_rust_RepeatedField_ $t _data >](f) }
+                }
+                fn mutable_data(f: RawRepeatedField) -> *mut Self {
+                    unsafe { [< __pb_rust_RepeatedField_ $t _mutable_data >](f) }
+                }
+                fn capacity(f: RawRepeatedField) -> usize {
+                    unsafe { [< __pb_rust_RepeatedField_ $t _capacity >](f) }
+                }
+                fn reserve(f: RawRepeatedField, size: usize) {
+                    unsafe { [< __pb_rust_RepeatedField_ $t _reserve >](f, size) }
+                }
+                fn shrink_to_fit(f: RawRepeatedField) {
+                    unsafe { [< __pb_rust_RepeatedField_ $t _shrink_to_fit >](f) }
+                }
+                fn clear(f: RawRepeatedField) {
+                    unsafe { [< __pb_rust_RepeatedField_ $t _clear >](f) }
+                }
+                fn truncate(f: RawRepeatedField, len: usize) {
+                    unsafe { [< __pb_rust_RepeatedField_ $t _truncate >](f, len) }
+                }
             }
         )* }
     };
@@ -274,6 +356,34 @@ macro_rules! impl_repeated_scalar_ops {
 
 impl_repeated_scalar_ops!(i32, u32, i64, u64, f32, f64, bool);
 
+/// A `RepeatedField<T>` that is not owned by any message. It is deleted when
+/// dropped.
+#[derive(Debug)]
+pub struct OwnedRepeatedField<T: ?Sized> {
+    raw: RawRepeatedField,
+    free: fn(RawRepeatedField),
+    _phantom: PhantomData<T>,
+}
+
+impl<T: RepeatedScalarOps> OwnedRepeatedField<T> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self { raw: T::new_repeated_field(), free: T::free_repeated_field, _phantom: PhantomData }
+    }
+}
+
+impl<T: ?Sized> OwnedRepeatedField<T> {
+    pub fn as_inner(&self) -> RepeatedFieldInner<'_> {
+        RepeatedFieldInner { raw: self.raw, _phantom: PhantomData }
+    }
+}
+
+impl<T: ?Sized> Drop for OwnedRepeatedField<T> {
+    fn drop(&mut self) {
+        (self.free)(self.raw)
+    }
+}
+
 impl<'msg, T: RepeatedScalarOps> RepeatedField<'msg, T> {
     #[allow(clippy::new_without_default, dead_code)]
     /// new() is not currently used in our normal pathways, it is only used
@@ -294,6 +404,41 @@ impl<'msg, T: RepeatedScalarOps> RepeatedField<'msg, T> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+    /// Returns the number of elements the field can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        T::capacity(self.inner.raw)
+    }
+    /// Reserves capacity for at least `additional` more elements.
+    pub fn reserve(&mut self, additional: usize) {
+        let size = self.len().checked_add(additional).expect("capacity overflow");
+        T::reserve(self.inner.raw, size)
+    }
+    /// Reallocates the field to hold exactly `len()` elements. Does nothing if
+    /// the field is allocated on an arena.
+    pub fn shrink_to_fit(&mut self) {
+        T::shrink_to_fit(self.inner.raw)
+    }
+    /// Removes all elements, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        T::clear(self.inner.raw)
+    }
+    /// Shortens the field to its first `len` elements. Does nothing if `len`
+    /// is not less than `self.len()`.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            T::truncate(self.inner.raw, len)
+        }
+    }
+    /// Appends all of `src`, reserving room for it up front.
+    pub fn extend_from_slice(&mut self, src: &[T])
+    where
+        T: Copy,
+    {
+        self.reserve(src.len());
+        for &val in src {
+            self.push(val);
+        }
+    }
     pub fn get(&self, index: usize) -> Option<T> {
         if index >= self.len() {
             return None;
@@ -306,6 +451,126 @@ impl<'msg, T: RepeatedScalarOps> RepeatedField<'msg, T> {
         }
         T::set(self.inner.raw, index, val)
     }
+    /// Sorts the elements in place, without allocating.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.as_mut_slice().sort_unstable()
+    }
+    /// Sorts the elements in place with `compare`. The sort is unstable: equal
+    /// elements may be reordered, and nothing is allocated.
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) {
+        self.as_mut_slice().sort_unstable_by(compare)
+    }
+    /// Keeps only the elements for which `f` returns `true`, in their original
+    /// order, compacting them in place.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let data = self.as_mut_slice();
+        let mut kept = 0;
+        for i in 0..data.len() {
+            if f(&data[i]) {
+                data.swap(kept, i);
+                kept += 1;
+            }
+        }
+        self.truncate(kept);
+    }
+    /// Replaces the elements in `range` with `replace_with`, shifting the
+    /// elements after it as needed, like `Vec::splice`.
+    ///
+    /// # Panics
+    /// Panics if `range` is decreasing or out of bounds.
+    pub fn splice(&mut self, range: Range<usize>, replace_with: &[T])
+    where
+        T: Copy,
+    {
+        let len = self.len();
+        assert!(
+            range.start <= range.end && range.end <= len,
+            "range {range:?} out of bounds for length {len}"
+        );
+        let removed = range.end - range.start;
+        let new_len = len - removed + replace_with.len();
+        if new_len > len {
+            // There is no thunk to resize without values, so the field is grown
+            // into reserved capacity with elements that are overwritten below.
+            self.extend_from_slice(&replace_with[..new_len - len]);
+        }
+        let slice = self.as_mut_slice();
+        slice.copy_within(range.end..len, range.start + replace_with.len());
+        slice[range.start..range.start + replace_with.len()].copy_from_slice(replace_with);
+        self.truncate(new_len);
+    }
+    /// Inserts `val` at `index`, shifting all elements after it up by one.
+    ///
+    /// # Panics
+    /// Panics if `index > len()`.
+    pub fn insert(&mut self, index: usize, val: T) {
+        let len = self.len();
+        assert!(index <= len, "insertion index {index} out of bounds for length {len}");
+        self.push(val);
+        self.as_mut_slice()[index..].rotate_right(1);
+    }
+    /// Removes and returns the element at `index`, shifting all elements after
+    /// it down by one. Returns `None` and leaves the field untouched if `index`
+    /// is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T>
+    where
+        T: Copy,
+    {
+        let removed = self.get(index)?;
+        let len = self.len();
+        self.as_mut_slice().copy_within(index + 1.., index);
+        T::truncate(self.inner.raw, len - 1);
+        Some(removed)
+    }
+    /// Removes and returns the element at `index`, replacing it with the last
+    /// element. Returns `None` and leaves the field untouched if `index` is
+    /// out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> Option<T> {
+        let removed = self.get(index)?;
+        let last = self.len() - 1;
+        T::set(self.inner.raw, index, T::get(self.inner.raw, last));
+        T::truncate(self.inner.raw, last);
+        Some(removed)
+    }
+    pub fn as_slice(&self) -> &[T] {
+        let len = self.len();
+        if len == 0 {
+            return &[];
+        }
+        // SAFETY:
+        // - `RepeatedField<T>::data()` points to `len` contiguous, initialized
+        //   elements of type `T`.
+        // - The field cannot be mutated while the returned slice borrows `self`.
+        unsafe { slice::from_raw_parts(T::data(self.inner.raw), len) }
+    }
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        let len = self.len();
+        if len == 0 {
+            return &mut [];
+        }
+        // SAFETY:
+        // - `RepeatedField<T>::mutable_data()` points to `len` contiguous,
+        //   initialized elements of type `T`.
+        // - The field cannot be otherwise accessed while the returned slice
+        //   mutably borrows `self`.
+        unsafe { slice::from_raw_parts_mut(T::mutable_data(self.inner.raw), len) }
+    }
+    /// Overwrites every element with the corresponding element of `src`.
+    ///
+    /// # Panics
+    /// Panics if `src.len() != self.len()`.
+    pub fn clone_from_slice(&mut self, src: &[T])
+    where
+        T: Copy,
+    {
+        assert_eq!(self.len(), src.len(), "destination and source slices have different lengths");
+        for (i, &val) in src.iter().enumerate() {
+            T::set(self.inner.raw, i, val)
+        }
+    }
     pub fn copy_from(&mut self, src: &RepeatedField<'_, T>) {
         T::copy_from(src.inner.raw, self.inner.raw)
     }
@@ -329,6 +594,34 @@ mod tests {
         let (ptr, len) = allocate_byte_array(b"Hello world");
         let serialized_data = SerializedData { data: NonNull::new(ptr).unwrap(), len: len };
         assert_eq!(&*serialized_data, b"Hello world");
+        assert_eq!(serialized_data.slice(2..5), Some(&b"llo"[..]));
+        assert_eq!(serialized_data.slice(5..12), None);
+    }
+
+    #[test]
+    fn test_serialized_data_into_vec() {
+        let data: &mut [u8] = Box::leak(Vec::from(&b"Hello world"[..]).into_boxed_slice());
+        let ptr = data.as_mut_ptr();
+        // SAFETY: `data` was allocated as a `Box<[u8]>` and was leaked above.
+        let serialized_data =
+            unsafe { SerializedData::from_raw_parts(NonNull::new(ptr).unwrap(), data.len()) };
+        let vec = serialized_data.into_vec();
+        assert_eq!(vec, b"Hello world");
+        assert_eq!(vec.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_serialized_data_concat() {
+        let (ptr, len) = allocate_byte_array(b"Hello ");
+        let first = SerializedData { data: NonNull::new(ptr).unwrap(), len };
+        let (ptr, len) = allocate_byte_array(b"world");
+        let second = SerializedData { data: NonNull::new(ptr).unwrap(), len };
+        let parts = [first, second];
+        let combined = SerializedData::concat(&parts);
+        assert_eq!(combined.len(), parts[0].len() + parts[1].len());
+        assert_eq!(&combined[..parts[0].len()], &*parts[0]);
+        assert_eq!(&*combined, b"Hello world");
+        assert_eq!(&*SerializedData::concat(&[]), b"");
     }
 
     #[test]
@@ -353,4 +646,151 @@ mod tests {
         r.push(true);
         assert_eq!(r.get(0), Some(true));
     }
+
+    #[test]
+    fn repeated_field_ref_into_iter() {
+        let mut r = RepeatedField::<u64>::new();
+        r.push(1);
+        r.push(2);
+        let mut iter = (&r).into_iter();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        assert_eq!((&r).into_iter().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn repeated_field_extend() {
+        let mut r = RepeatedField::<i32>::new();
+        r.extend_from_slice(&[1, 2]);
+        assert_eq!(r.as_slice(), [1, 2]);
+        r.extend([3, 4]);
+        r.extend_from_slice(&[5]);
+        assert_eq!(r.as_slice(), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn repeated_field_get_mut() {
+        let mut r = RepeatedField::<i32>::new();
+        r.push(1);
+        r.push(2);
+        let mut mutator = crate::RepeatedMut::<i32>::from_inner(Private, r.inner);
+        mutator.get_mut(1).unwrap().set(5);
+        assert!(mutator.get_mut(2).is_none());
+        assert_eq!(r.as_slice(), [1, 5]);
+    }
+
+    #[test]
+    fn repeated_field_swap_remove() {
+        let mut r = RepeatedField::<i32>::new();
+        for i in 0..4 {
+            r.push(i);
+        }
+        assert_eq!(r.swap_remove(4), None);
+        assert_eq!(r.swap_remove(1), Some(1));
+        assert_eq!(r.as_slice(), [0, 3, 2]);
+        assert_eq!(r.swap_remove(2), Some(2));
+        assert_eq!(r.as_slice(), [0, 3]);
+    }
+
+    #[test]
+    fn repeated_field_remove() {
+        let mut r = RepeatedField::<i32>::new();
+        for i in 0..4 {
+            r.push(i);
+        }
+        assert_eq!(r.remove(4), None);
+        assert_eq!(r.remove(1), Some(1));
+        assert_eq!(r.as_slice(), [0, 2, 3]);
+        assert_eq!(r.remove(2), Some(3));
+        assert_eq!(r.as_slice(), [0, 2]);
+    }
+
+    #[test]
+    fn repeated_field_insert() {
+        let mut r = RepeatedField::<i32>::new();
+        r.insert(0, 2);
+        r.insert(0, 0);
+        r.insert(1, 1);
+        r.insert(3, 3);
+        assert_eq!(r.as_slice(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn repeated_field_splice() {
+        let mut r = RepeatedField::<i32>::new();
+        r.extend_from_slice(&[1, 2, 3, 4]);
+        r.splice(1..3, &[9, 9, 9]);
+        assert_eq!(r.as_slice(), [1, 9, 9, 9, 4]);
+        r.splice(0..4, &[5]);
+        assert_eq!(r.as_slice(), [5, 4]);
+    }
+
+    #[test]
+    fn repeated_field_retain() {
+        let mut r = RepeatedField::<i32>::new();
+        r.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        r.retain(|x| x % 2 == 1);
+        assert_eq!(r.as_slice(), [1, 3, 5]);
+    }
+
+    #[test]
+    fn repeated_field_sort() {
+        let mut r = RepeatedField::<i32>::new();
+        r.extend_from_slice(&[3, -1, 2]);
+        r.sort();
+        assert_eq!(r.as_slice(), [-1, 2, 3]);
+
+        let mut r = RepeatedField::<f32>::new();
+        r.extend_from_slice(&[2.5, -0.5, 1.0]);
+        r.sort_by(f32::total_cmp);
+        assert_eq!(r.as_slice(), [-0.5, 1.0, 2.5]);
+    }
+
+    #[test]
+    fn repeated_field_reserve() {
+        let mut r = RepeatedField::<i32>::new();
+        r.reserve(100);
+        assert!(r.capacity() >= 100);
+        assert_eq!(r.len(), 0);
+    }
+
+    #[test]
+    fn repeated_field_shrink_to_fit() {
+        let mut r = RepeatedField::<i32>::new();
+        r.reserve(100);
+        r.push(1);
+        r.push(2);
+        r.shrink_to_fit();
+        assert!(r.capacity() < 100);
+        assert_eq!(r.as_slice(), [1, 2]);
+    }
+
+    #[test]
+    fn repeated_field_clear() {
+        let mut r = RepeatedField::<i32>::new();
+        r.clear();
+        assert_eq!(r.len(), 0);
+        r.push(1);
+        r.push(2);
+        r.clear();
+        assert_eq!(r.len(), 0);
+        assert_eq!(r.get(0), None);
+    }
+
+    #[test]
+    fn repeated_field_truncate() {
+        let mut r = RepeatedField::<i32>::new();
+        for i in 0..4 {
+            r.push(i);
+        }
+        r.truncate(5);
+        assert_eq!(r.as_slice(), [0, 1, 2, 3]);
+        r.truncate(4);
+        assert_eq!(r.as_slice(), [0, 1, 2, 3]);
+        r.truncate(2);
+        assert_eq!(r.as_slice(), [0, 1]);
+        r.truncate(0);
+        assert_eq!(r.len(), 0);
+    }
 }
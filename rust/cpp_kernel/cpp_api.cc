@@ -6,6 +6,9 @@ extern "C" {
   google::protobuf::RepeatedField<ty>* __pb_rust_RepeatedField_##rust_ty##_new() {      \
     return new google::protobuf::RepeatedField<ty>();                                   \
   }                                                                           \
+  void __pb_rust_RepeatedField_##rust_ty##_free(google::protobuf::RepeatedField<ty>* r) { \
+    delete r;                                                                 \
+  }                                                                           \
   void __pb_rust_RepeatedField_##rust_ty##_add(google::protobuf::RepeatedField<ty>* r,  \
                                                ty val) {                      \
     r->Add(val);                                                              \
@@ -25,6 +28,37 @@ extern "C" {
   void __pb_rust_RepeatedField_##rust_ty##_copy_from(                         \
       google::protobuf::RepeatedField<ty> const& src, google::protobuf::RepeatedField<ty>& dst) { \
     dst.CopyFrom(src);                                                        \
+  }                                                                           \
+  const ty* __pb_rust_RepeatedField_##rust_ty##_data(                         \
+      google::protobuf::RepeatedField<ty>* r) {                                         \
+    return r->data();                                                         \
+  }                                                                           \
+  ty* __pb_rust_RepeatedField_##rust_ty##_mutable_data(                       \
+      google::protobuf::RepeatedField<ty>* r) {                                         \
+    return r->mutable_data();                                                 \
+  }                                                                           \
+  size_t __pb_rust_RepeatedField_##rust_ty##_capacity(                        \
+      google::protobuf::RepeatedField<ty>* r) {                                         \
+    return r->Capacity();                                                     \
+  }                                                                           \
+  void __pb_rust_RepeatedField_##rust_ty##_reserve(                           \
+      google::protobuf::RepeatedField<ty>* r, size_t size) {                            \
+    r->Reserve(static_cast<int>(size));                                       \
+  }                                                                           \
+  void __pb_rust_RepeatedField_##rust_ty##_shrink_to_fit(                     \
+      google::protobuf::RepeatedField<ty>* r) {                                         \
+    /* Arena memory is only freed with the arena; a copy would not shrink. */ \
+    if (r->GetArena() != nullptr) return;                                     \
+    google::protobuf::RepeatedField<ty> shrunk(r->begin(), r->end());                   \
+    r->Swap(&shrunk);                                                         \
+  }                                                                           \
+  void __pb_rust_RepeatedField_##rust_ty##_clear(                             \
+      google::protobuf::RepeatedField<ty>* r) {                                         \
+    r->Clear();                                                               \
+  }                                                                           \
+  void __pb_rust_RepeatedField_##rust_ty##_truncate(                          \
+      google::protobuf::RepeatedField<ty>* r, size_t len) {                             \
+    r->Truncate(static_cast<int>(len));                                       \
   }
 
 expose_repeated_field_methods(int32_t, i32);
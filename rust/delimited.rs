@@ -0,0 +1,165 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Reading of length-delimited message streams: each message is preceded by
+//! its length as a varint, as written by C++ `SerializeDelimitedToOstream`
+//! and Java `writeDelimitedTo`.
+
+use crate::__internal::Private;
+use crate::ParseError;
+use std::io::{self, Read};
+
+/// An iterator over the serialized messages of a length-delimited stream.
+///
+/// Stops at a clean end of input between two messages. Input that ends in
+/// the middle of a length or a message, or any I/O error, yields a single
+/// `Err` and ends the iteration.
+#[derive(Debug)]
+pub struct DelimitedFrames<R> {
+    reader: R,
+    done: bool,
+}
+
+impl<R: Read> DelimitedFrames<R> {
+    pub fn new(_private: Private, reader: R) -> Self {
+        Self { reader, done: false }
+    }
+
+    /// Reads one byte, retrying interrupted reads, or `None` at end of input.
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Reads the length prefix of the next message, or `None` at a clean end
+    /// of input.
+    fn read_len(&mut self) -> Result<Option<u64>, ParseError> {
+        let mut len = 0u64;
+        for i in 0..10 {
+            let byte = match self.read_byte() {
+                Ok(Some(byte)) => byte,
+                Ok(None) if i == 0 => return Ok(None),
+                _ => return Err(ParseError),
+            };
+            len |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(Some(len));
+            }
+        }
+        // A varint is at most 10 bytes long.
+        Err(ParseError)
+    }
+
+    fn read_frame(&mut self) -> Option<Result<Vec<u8>, ParseError>> {
+        let len = match self.read_len() {
+            Ok(Some(len)) => len,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        // Read through `take` rather than allocating `len` bytes up front, so a
+        // corrupt length cannot trigger a huge allocation.
+        let mut frame = Vec::new();
+        match (&mut self.reader).take(len).read_to_end(&mut frame) {
+            Ok(n) if n as u64 == len => Some(Ok(frame)),
+            _ => Some(Err(ParseError)),
+        }
+    }
+}
+
+impl<R: Read> Iterator for DelimitedFrames<R> {
+    type Item = Result<Vec<u8>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = self.read_frame();
+        self.done = !matches!(frame, Some(Ok(_)));
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(data: &[u8]) -> Vec<Result<Vec<u8>, ParseError>> {
+        DelimitedFrames::new(Private, data).collect()
+    }
+
+    #[test]
+    fn test_empty_stream() {
+        assert!(frames(b"").is_empty());
+    }
+
+    #[test]
+    fn test_frames() {
+        let got = frames(b"\x02ab\x00\x03cde");
+        let got: Vec<_> = got.into_iter().map(Result::unwrap).collect();
+        assert_eq!(got, [&b"ab"[..], b"", b"cde"]);
+    }
+
+    #[test]
+    fn test_multi_byte_len() {
+        let mut data = vec![0x81, 0x01];
+        data.extend([7u8; 129]);
+        let got = frames(&data);
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].as_ref().unwrap().len(), 129);
+    }
+
+    #[test]
+    fn test_truncated_message() {
+        let got = frames(b"\x02ab\x05abc");
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[0].as_ref().unwrap(), b"ab");
+        assert!(got[1].is_err());
+    }
+
+    /// Fails every other read with `Interrupted`, starting with the first.
+    struct InterruptingReader<'a> {
+        data: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl Read for InterruptingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            self.data.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_interrupted_reads() {
+        let interrupting = |data| InterruptingReader { data, interrupt: false };
+        assert_eq!(DelimitedFrames::new(Private, interrupting(b"")).count(), 0);
+
+        let mut data = vec![0x81, 0x01];
+        data.extend([7u8; 129]);
+        data.extend(b"\x02ab");
+        let got: Vec<_> =
+            DelimitedFrames::new(Private, interrupting(&data)).map(Result::unwrap).collect();
+        assert_eq!(got, [&[7u8; 129][..], b"ab"]);
+    }
+
+    #[test]
+    fn test_truncated_len() {
+        let got = frames(b"\x01a\x80");
+        assert_eq!(got.len(), 2);
+        assert!(got[1].is_err());
+    }
+}
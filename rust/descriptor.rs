@@ -0,0 +1,232 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Runtime descriptors, for handling message types that are only known at
+//! runtime.
+
+use crate::__internal::RawMessageDef;
+use crate::__runtime::{
+    message_def_field_count, message_def_field_default, message_def_full_name, DefPoolInner,
+};
+use crate::DynamicValue;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A set of descriptors loaded from serialized `FileDescriptorProto`s.
+///
+/// Files must be added in dependency order: a file can only refer to types
+/// from files that were already added.
+#[derive(Debug)]
+pub struct DefPool {
+    inner: DefPoolInner,
+}
+
+impl DefPool {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self { inner: DefPoolInner::new() }
+    }
+
+    /// Adds a serialized `FileDescriptorProto` to the pool.
+    pub fn add_serialized_file_descriptor(&mut self, bytes: &[u8]) -> Result<(), DescriptorError> {
+        self.inner.add_serialized_file(bytes).map_err(|message| DescriptorError { message })
+    }
+
+    /// Adds every file of a serialized `FileDescriptorSet`, in the order they
+    /// appear, as written by `protoc --include_imports --descriptor_set_out`.
+    pub fn add_serialized_file_descriptor_set(
+        &mut self,
+        mut bytes: &[u8],
+    ) -> Result<(), DescriptorError> {
+        let malformed = || DescriptorError { message: "malformed FileDescriptorSet".into() };
+        while !bytes.is_empty() {
+            // `FileDescriptorSet` only has `repeated FileDescriptorProto file = 1`.
+            if read_varint(&mut bytes).ok_or_else(malformed)? != (1 << 3) | 2 {
+                return Err(malformed());
+            }
+            let len = read_varint(&mut bytes).ok_or_else(malformed)?;
+            let len = usize::try_from(len).ok().filter(|&len| len <= bytes.len());
+            let (file, rest) = bytes.split_at(len.ok_or_else(malformed)?);
+            self.add_serialized_file_descriptor(file)?;
+            bytes = rest;
+        }
+        Ok(())
+    }
+
+    /// Finds a message type by its fully-qualified name, e.g. `"pkg.MyMessage"`.
+    pub fn find_message(&self, full_name: &str) -> Option<MessageDescriptor<'_>> {
+        let raw = self.inner.find_message(full_name)?;
+        Some(MessageDescriptor { raw, _phantom: PhantomData })
+    }
+}
+
+/// Reads a varint from the front of `bytes`, advancing past it.
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut val = 0u64;
+    for i in 0..10 {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        val |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some(val);
+        }
+    }
+    None
+}
+
+/// A message type owned by a [`DefPool`].
+#[derive(Clone, Copy)]
+pub struct MessageDescriptor<'pool> {
+    raw: RawMessageDef,
+    _phantom: PhantomData<&'pool DefPool>,
+}
+
+impl<'pool> MessageDescriptor<'pool> {
+    pub(crate) fn raw(&self) -> RawMessageDef {
+        self.raw
+    }
+
+    /// The fully-qualified name of this message type.
+    pub fn full_name(&self) -> &'pool str {
+        // SAFETY: `self.raw` is owned by a pool that outlives `'pool`.
+        unsafe { message_def_full_name(self.raw) }
+    }
+
+    /// The number of fields declared on this message type.
+    pub fn field_count(&self) -> usize {
+        // SAFETY: `self.raw` is owned by a pool that outlives `'pool`.
+        unsafe { message_def_field_count(self.raw) }
+    }
+
+    /// The declared default of the singular field named `name`: the
+    /// `[default = ...]` option if there is one, or else the zero value of the
+    /// field's type. Returns `None` if there is no such field, or it is a
+    /// message field.
+    pub fn field_default(&self, name: &str) -> Option<DynamicValue<'pool>> {
+        // SAFETY: `self.raw` is owned by a pool that outlives `'pool`.
+        unsafe { message_def_field_default(self.raw, name) }
+    }
+}
+
+impl fmt::Debug for MessageDescriptor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MessageDescriptor").field(&self.full_name()).finish()
+    }
+}
+
+/// An error that happened while building descriptors.
+#[derive(Debug, Clone)]
+pub struct DescriptorError {
+    message: String,
+}
+
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DescriptorError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use googletest::prelude::*;
+
+    // FileDescriptorProto {
+    //   name: "test.proto"
+    //   package: "pkg"
+    //   message_type {
+    //     name: "Foo"
+    //     field { name: "a" number: 1 label: LABEL_OPTIONAL type: TYPE_INT32 }
+    //   }
+    // }
+    const TEST_FILE: &[u8] = b"\x0a\x0atest.proto\x12\x03pkg\
+        \x22\x10\x0a\x03Foo\x12\x09\x0a\x01a\x18\x01\x20\x01\x28\x05";
+
+    #[test]
+    fn test_find_message() {
+        let mut pool = DefPool::new();
+        assert_that!(pool.find_message("pkg.Foo").is_none(), eq(true));
+
+        assert_that!(pool.add_serialized_file_descriptor(TEST_FILE).is_ok(), eq(true));
+        let foo = pool.find_message("pkg.Foo").expect("pkg.Foo to be found");
+        assert_that!(foo.full_name(), eq("pkg.Foo"));
+        assert_that!(foo.field_count(), eq(1));
+        assert_that!(pool.find_message("Foo").is_none(), eq(true));
+    }
+
+    // FileDescriptorProto {
+    //   name: "test.proto"
+    //   package: "pkg"
+    //   message_type {
+    //     name: "Bar"
+    //     field {
+    //       name: "default_int32" number: 1 label: LABEL_OPTIONAL type: TYPE_INT32
+    //       default_value: "41"
+    //     }
+    //     field {
+    //       name: "default_string" number: 2 label: LABEL_OPTIONAL type: TYPE_STRING
+    //       default_value: "hello"
+    //     }
+    //     field { name: "c" number: 3 label: LABEL_OPTIONAL type: TYPE_INT32 }
+    //   }
+    // }
+    const DEFAULTS_FILE: &[u8] = b"\x0a\x0atest.proto\x12\x03pkg\
+        \x22\x4a\x0a\x03Bar\
+        \x12\x19\x0a\x0ddefault_int32\x18\x01\x20\x01\x28\x05\x3a\x0241\
+        \x12\x1d\x0a\x0edefault_string\x18\x02\x20\x01\x28\x09\x3a\x05hello\
+        \x12\x09\x0a\x01c\x18\x03\x20\x01\x28\x05";
+
+    #[test]
+    fn test_field_default() {
+        let mut pool = DefPool::new();
+        pool.add_serialized_file_descriptor(DEFAULTS_FILE).unwrap();
+        let bar = pool.find_message("pkg.Bar").unwrap();
+        assert_that!(bar.field_default("default_int32"), eq(Some(DynamicValue::I32(41))));
+        assert_that!(
+            bar.field_default("default_string"),
+            eq(Some(DynamicValue::String("hello".into())))
+        );
+        assert_that!(bar.field_default("c"), eq(Some(DynamicValue::I32(0))));
+        assert_that!(bar.field_default("d"), eq(None));
+    }
+
+    #[test]
+    fn test_add_invalid_file() {
+        let mut pool = DefPool::new();
+        assert_that!(pool.add_serialized_file_descriptor(b"\xff").is_err(), eq(true));
+
+        assert_that!(pool.add_serialized_file_descriptor(TEST_FILE).is_ok(), eq(true));
+        // Files can only be added once.
+        assert_that!(pool.add_serialized_file_descriptor(TEST_FILE).is_err(), eq(true));
+    }
+
+    #[test]
+    fn test_add_file_descriptor_set() {
+        let mut set = vec![0x0a, TEST_FILE.len() as u8];
+        set.extend_from_slice(TEST_FILE);
+        let mut pool = DefPool::new();
+        assert_that!(pool.add_serialized_file_descriptor_set(&set).is_ok(), eq(true));
+        assert_that!(pool.find_message("pkg.Foo").is_some(), eq(true));
+
+        let mut pool = DefPool::new();
+        assert_that!(pool.add_serialized_file_descriptor_set(&set[..10]).is_err(), eq(true));
+        assert_that!(pool.add_serialized_file_descriptor_set(b"\x08\x01").is_err(), eq(true));
+    }
+
+    #[test]
+    fn test_error_converts_to_crate_error() {
+        fn add(pool: &mut DefPool, bytes: &[u8]) -> Result<(), crate::Error> {
+            pool.add_serialized_file_descriptor(bytes)?;
+            Ok(())
+        }
+        let err = add(&mut DefPool::new(), b"\xff").unwrap_err();
+        assert_that!(matches!(err, crate::Error::Descriptor(_)), eq(true));
+        assert_that!(err.to_string(), eq("Couldn't build descriptors"));
+    }
+}
@@ -0,0 +1,288 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Messages whose type is only known at runtime, through a
+//! [`MessageDescriptor`].
+
+use crate::__runtime::{DynamicMessageInner, SerializedData};
+use crate::{Error, MessageDescriptor, ParseError, ProtoStr};
+use std::fmt;
+
+/// A message of a type described by a [`MessageDescriptor`], without the
+/// generated code for that type being linked in.
+#[derive(Debug)]
+pub struct DynamicMessage<'pool> {
+    inner: DynamicMessageInner,
+    descriptor: MessageDescriptor<'pool>,
+}
+
+impl<'pool> DynamicMessage<'pool> {
+    /// Creates an empty message of the given type.
+    pub fn new(descriptor: MessageDescriptor<'pool>) -> Self {
+        // SAFETY: `descriptor` is owned by a pool that outlives `'pool`.
+        let inner = unsafe { DynamicMessageInner::new(descriptor.raw()) };
+        Self { inner, descriptor }
+    }
+
+    /// Creates a message of the given type with each `(name, value)` pair
+    /// set as by [`Self::set_field_by_name`], in order. Handy for building
+    /// test inputs.
+    pub fn from_fields(
+        descriptor: MessageDescriptor<'pool>,
+        fields: &[(&str, DynamicValue<'_>)],
+    ) -> Result<Self, FieldError> {
+        let mut msg = Self::new(descriptor);
+        for &(name, val) in fields {
+            // SAFETY: `msg.inner` was created with `descriptor`.
+            unsafe { msg.inner.set(descriptor.raw(), name, val) }?;
+        }
+        Ok(msg)
+    }
+
+    /// Parses `data` as a message of the given type.
+    pub fn parse(descriptor: MessageDescriptor<'pool>, data: &[u8]) -> Result<Self, ParseError> {
+        let mut msg = Self::new(descriptor);
+        // SAFETY: `msg.inner` was created with `descriptor`.
+        unsafe { msg.inner.parse(descriptor.raw(), data) }?;
+        Ok(msg)
+    }
+
+    pub fn serialize(&self) -> SerializedData {
+        // SAFETY: `self.inner` was created with `self.descriptor`.
+        unsafe { self.inner.serialize(self.descriptor.raw()) }
+    }
+
+    pub fn descriptor(&self) -> MessageDescriptor<'pool> {
+        self.descriptor
+    }
+
+    /// Returns the value of the singular scalar, string, or bytes field named
+    /// `name`. Returns `None` if there is no such field on this message type,
+    /// or if it is a repeated, map, or message field.
+    pub fn get_field_by_name(&self, name: &str) -> Option<DynamicValue<'_>> {
+        // SAFETY: `self.inner` was created with `self.descriptor`.
+        unsafe { self.inner.get(self.descriptor.raw(), name) }
+    }
+
+    /// Sets the singular scalar, string, or bytes field named `name`.
+    ///
+    /// Returns an error, leaving the message unchanged, if there is no such
+    /// field or `val` is not of the field's type. Enum fields take an
+    /// [`DynamicValue::I32`].
+    pub fn set_field_by_name(
+        &mut self,
+        name: &str,
+        val: DynamicValue<'_>,
+    ) -> Result<(), FieldError> {
+        // SAFETY: `self.inner` was created with `self.descriptor`.
+        unsafe { self.inner.set(self.descriptor.raw(), name, val) }
+    }
+
+    /// Clears the repeated or map field named `name`.
+    pub fn clear_repeated_field_by_name(&mut self, name: &str) -> Result<(), FieldError> {
+        // SAFETY: `self.inner` was created with `self.descriptor`.
+        unsafe { self.inner.clear_repeated(self.descriptor.raw(), name) }
+    }
+}
+
+/// Decodes `bytes` as a message of the given type and re-encodes it as
+/// compact JSON, without going through generated code.
+///
+/// The output is upb's JSON encoding with default options: keys are the
+/// fields' `json_name`s, 64-bit integers are quoted, and unset fields are
+/// omitted. Generated messages have no JSON serializer of their own, so this
+/// is the only JSON path the crate offers.
+pub fn transcode_wire_to_json(
+    bytes: &[u8],
+    descriptor: MessageDescriptor<'_>,
+) -> Result<String, TranscodeError> {
+    let msg = DynamicMessage::parse(descriptor, bytes).map_err(TranscodeError::Parse)?;
+    // SAFETY: `msg.inner` was created with `descriptor`.
+    unsafe { msg.inner.to_json(descriptor.raw()) }.map_err(TranscodeError::Json)
+}
+
+/// An error that happened while transcoding between formats.
+#[derive(Debug, Clone)]
+pub enum TranscodeError {
+    /// The input was not a valid serialized message.
+    Parse(ParseError),
+    /// The message could not be represented as JSON.
+    Json(String),
+}
+
+impl fmt::Display for TranscodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscodeError::Parse(e) => fmt::Display::fmt(e, f),
+            TranscodeError::Json(msg) => write!(f, "Couldn't encode message as JSON: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for TranscodeError {}
+
+impl From<TranscodeError> for Error {
+    fn from(e: TranscodeError) -> Self {
+        match e {
+            TranscodeError::Parse(e) => Error::Parse(e),
+            TranscodeError::Json(msg) => Error::Json(msg),
+        }
+    }
+}
+
+/// An error from looking up a field of a [`DynamicMessage`] by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldError {
+    /// The message type has no field with the given name.
+    NotFound,
+    /// The field exists, but is not repeated.
+    NotRepeated,
+    /// The field exists, but cannot hold the given value.
+    WrongType,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::NotFound => write!(f, "No field with that name"),
+            FieldError::NotRepeated => write!(f, "Field is not repeated"),
+            FieldError::WrongType => write!(f, "Value does not match the field's type"),
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+/// The value of a singular field of a [`DynamicMessage`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DynamicValue<'a> {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(&'a ProtoStr),
+    Bytes(&'a [u8]),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefPool;
+    use googletest::prelude::*;
+
+    // A serialized `FileDescriptorProto` for:
+    //   syntax = "proto2";
+    //   package pkg;
+    //   message Foo {
+    //     optional int32 a = 1;
+    //     optional string b = 2;
+    //     repeated int32 c = 3;
+    //   }
+    const TEST_FILE: &[u8] = b"\x0a\x0atest.proto\x12\x03pkg\
+        \x22\x26\x0a\x03Foo\
+        \x12\x09\x0a\x01a\x18\x01\x20\x01\x28\x05\
+        \x12\x09\x0a\x01b\x18\x02\x20\x01\x28\x09\
+        \x12\x09\x0a\x01c\x18\x03\x20\x03\x28\x05";
+
+    fn test_pool() -> DefPool {
+        let mut pool = DefPool::new();
+        pool.add_serialized_file_descriptor(TEST_FILE).unwrap();
+        pool
+    }
+
+    #[test]
+    fn parse_and_get_by_name() {
+        let pool = test_pool();
+        let foo = pool.find_message("pkg.Foo").unwrap();
+        // `a: 42`, as serialized by generated code.
+        let msg = DynamicMessage::parse(foo, b"\x08\x2a").unwrap();
+        assert_that!(msg.get_field_by_name("a"), eq(Some(DynamicValue::I32(42))));
+        assert_that!(msg.get_field_by_name("b"), eq(Some(DynamicValue::String("".into()))));
+        assert_that!(msg.get_field_by_name("d"), eq(None));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let pool = test_pool();
+        let foo = pool.find_message("pkg.Foo").unwrap();
+        assert!(DynamicMessage::parse(foo, b"\x08").is_err());
+    }
+
+    #[test]
+    fn set_by_name_and_serialize() {
+        let pool = test_pool();
+        let foo = pool.find_message("pkg.Foo").unwrap();
+        let mut msg = DynamicMessage::new(foo);
+        assert_that!(msg.set_field_by_name("a", DynamicValue::I32(7)), eq(Ok(())));
+        assert_that!(msg.set_field_by_name("b", DynamicValue::String("hi".into())), eq(Ok(())));
+        assert_that!(
+            msg.set_field_by_name("a", DynamicValue::U64(7)),
+            eq(Err(FieldError::WrongType))
+        );
+        assert_that!(
+            msg.set_field_by_name("d", DynamicValue::I32(7)),
+            eq(Err(FieldError::NotFound))
+        );
+        assert_that!(
+            msg.set_field_by_name("c", DynamicValue::I32(7)),
+            eq(Err(FieldError::WrongType))
+        );
+        assert_that!(&*msg.serialize(), eq(b"\x08\x07\x12\x02hi"));
+
+        let parsed = DynamicMessage::parse(foo, &msg.serialize()).unwrap();
+        assert_that!(parsed.get_field_by_name("a"), eq(Some(DynamicValue::I32(7))));
+        assert_that!(parsed.get_field_by_name("b"), eq(Some(DynamicValue::String("hi".into()))));
+    }
+
+    #[test]
+    fn from_fields() {
+        let pool = test_pool();
+        let foo = pool.find_message("pkg.Foo").unwrap();
+        let msg = DynamicMessage::from_fields(
+            foo,
+            &[("a", DynamicValue::I32(7)), ("b", DynamicValue::String("hi".into()))],
+        )
+        .unwrap();
+        assert_that!(msg.get_field_by_name("a"), eq(Some(DynamicValue::I32(7))));
+        assert_that!(msg.get_field_by_name("b"), eq(Some(DynamicValue::String("hi".into()))));
+
+        let err = DynamicMessage::from_fields(foo, &[("d", DynamicValue::I32(7))]).unwrap_err();
+        assert_that!(err, eq(FieldError::NotFound));
+        let err = DynamicMessage::from_fields(foo, &[("a", DynamicValue::U64(7))]).unwrap_err();
+        assert_that!(err, eq(FieldError::WrongType));
+        let err = DynamicMessage::from_fields(foo, &[("c", DynamicValue::I32(7))]).unwrap_err();
+        assert_that!(err, eq(FieldError::WrongType));
+    }
+
+    #[test]
+    fn clear_repeated_by_name() {
+        let pool = test_pool();
+        let foo = pool.find_message("pkg.Foo").unwrap();
+        // `a: 1, c: [1, 2]`.
+        let mut msg = DynamicMessage::parse(foo, b"\x08\x01\x18\x01\x18\x02").unwrap();
+        assert_that!(msg.clear_repeated_field_by_name("c"), eq(Ok(())));
+        assert_that!(&*msg.serialize(), eq(b"\x08\x01"));
+        assert_that!(msg.clear_repeated_field_by_name("a"), eq(Err(FieldError::NotRepeated)));
+        assert_that!(msg.clear_repeated_field_by_name("d"), eq(Err(FieldError::NotFound)));
+        assert_that!(msg.get_field_by_name("a"), eq(Some(DynamicValue::I32(1))));
+    }
+
+    #[test]
+    fn transcode_to_json() {
+        let pool = test_pool();
+        let foo = pool.find_message("pkg.Foo").unwrap();
+        assert_that!(transcode_wire_to_json(b"", foo).unwrap(), eq("{}"));
+        assert_that!(
+            transcode_wire_to_json(b"\x08\x2a\x12\x02hi", foo).unwrap(),
+            eq(r#"{"a":42,"b":"hi"}"#)
+        );
+        assert!(matches!(transcode_wire_to_json(b"\x08", foo), Err(TranscodeError::Parse(_))));
+    }
+}
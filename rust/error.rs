@@ -0,0 +1,174 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! A single error type that the more specific errors of this crate convert
+//! into, for callers that just want to propagate failures with `?`.
+
+use crate::{AllocError, LengthMismatch, ParseError};
+#[cfg(upb_kernel)]
+use crate::{DescriptorError, FieldError};
+use std::fmt;
+use std::io;
+
+/// Any error returned by an operation on a message.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input was not a valid serialized message.
+    Parse(ParseError),
+    /// The message could not be serialized.
+    Serialize(String),
+    /// The message could not be converted to or from JSON.
+    Json(String),
+    /// The message could not be converted to or from text format.
+    TextFormat(String),
+    /// Reading or writing an underlying stream failed.
+    Io(io::Error),
+    /// Memory could not be allocated.
+    Alloc(AllocError),
+    /// Two inputs that must be the same length were not.
+    LengthMismatch(LengthMismatch),
+    /// Descriptors could not be built.
+    #[cfg(upb_kernel)]
+    Descriptor(DescriptorError),
+    /// A field of a dynamic message could not be accessed.
+    #[cfg(upb_kernel)]
+    Field(FieldError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(_) => write!(f, "Couldn't parse message"),
+            Error::Serialize(msg) => write!(f, "Couldn't serialize message: {msg}"),
+            Error::Json(msg) => write!(f, "Couldn't convert message to or from JSON: {msg}"),
+            Error::TextFormat(msg) => {
+                write!(f, "Couldn't convert message to or from text format: {msg}")
+            }
+            Error::Io(_) => write!(f, "Couldn't read or write message"),
+            Error::Alloc(_) => write!(f, "Couldn't allocate memory for message"),
+            Error::LengthMismatch(_) => write!(f, "Couldn't combine inputs of different lengths"),
+            #[cfg(upb_kernel)]
+            Error::Descriptor(_) => write!(f, "Couldn't build descriptors"),
+            #[cfg(upb_kernel)]
+            Error::Field(_) => write!(f, "Couldn't access field of dynamic message"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Alloc(e) => Some(e),
+            Error::LengthMismatch(e) => Some(e),
+            #[cfg(upb_kernel)]
+            Error::Descriptor(e) => Some(e),
+            #[cfg(upb_kernel)]
+            Error::Field(e) => Some(e),
+            Error::Serialize(_) | Error::Json(_) | Error::TextFormat(_) => None,
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<AllocError> for Error {
+    fn from(e: AllocError) -> Self {
+        Error::Alloc(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<LengthMismatch> for Error {
+    fn from(e: LengthMismatch) -> Self {
+        Error::LengthMismatch(e)
+    }
+}
+
+#[cfg(upb_kernel)]
+impl From<DescriptorError> for Error {
+    fn from(e: DescriptorError) -> Self {
+        Error::Descriptor(e)
+    }
+}
+
+#[cfg(upb_kernel)]
+impl From<FieldError> for Error {
+    fn from(e: FieldError) -> Self {
+        Error::Field(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(upb_kernel)]
+    use crate::DefPool;
+    use std::error::Error as _;
+
+    #[cfg(upb_kernel)]
+    fn descriptor_error() -> DescriptorError {
+        DefPool::new().add_serialized_file_descriptor_set(b"\x0a").unwrap_err()
+    }
+
+    #[test]
+    fn display() {
+        let cases = [
+            (Error::from(ParseError), "Couldn't parse message"),
+            (Error::Serialize("too big".into()), "Couldn't serialize message: too big"),
+            (Error::Json("bad".into()), "Couldn't convert message to or from JSON: bad"),
+            (
+                Error::TextFormat("bad".into()),
+                "Couldn't convert message to or from text format: bad",
+            ),
+            (Error::from(io::Error::other("eof")), "Couldn't read or write message"),
+            (Error::from(AllocError), "Couldn't allocate memory for message"),
+            (Error::from(LengthMismatch), "Couldn't combine inputs of different lengths"),
+            #[cfg(upb_kernel)]
+            (Error::from(descriptor_error()), "Couldn't build descriptors"),
+            #[cfg(upb_kernel)]
+            (Error::from(FieldError::NotFound), "Couldn't access field of dynamic message"),
+        ];
+        for (err, want) in cases {
+            assert_eq!(err.to_string(), want);
+        }
+    }
+
+    #[test]
+    fn source() {
+        let err = Error::from(ParseError);
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "Couldn't deserialize given bytes into a proto"
+        );
+        let err = Error::from(io::Error::other("eof"));
+        assert_eq!(err.source().unwrap().to_string(), "eof");
+        let err = Error::from(AllocError);
+        assert_eq!(err.source().unwrap().to_string(), "Couldn't allocate memory");
+        let err = Error::from(LengthMismatch);
+        assert_eq!(err.source().unwrap().to_string(), "Inputs have different lengths");
+        #[cfg(upb_kernel)]
+        {
+            let err = Error::from(descriptor_error());
+            assert_eq!(err.source().unwrap().to_string(), "malformed FileDescriptorSet");
+            let err = Error::from(FieldError::NotRepeated);
+            assert_eq!(err.source().unwrap().to_string(), "Field is not repeated");
+        }
+        assert!(Error::Json("bad".into()).source().is_none());
+    }
+}
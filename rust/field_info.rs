@@ -0,0 +1,37 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Static descriptions of the fields of generated messages, for checking
+//! schema compatibility at runtime without a descriptor.
+
+/// The kind of value a field holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// A bool, integer, or floating-point field.
+    Scalar,
+    Enum,
+    String,
+    Bytes,
+    /// A message or group field.
+    Message,
+    Map,
+}
+
+/// One field of a generated message, as returned by the generated `fields()`
+/// accessor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub number: u32,
+    /// The name of the field as written in the `.proto` file.
+    pub name: &'static str,
+    pub kind: FieldKind,
+    /// Whether the field is repeated. Map fields are not.
+    pub repeated: bool,
+    /// Whether the field is part of a oneof. The implicit oneofs of proto3
+    /// `optional` fields do not count.
+    pub in_oneof: bool,
+}
@@ -8,7 +8,9 @@
 use crate::{
     __internal::Private,
     __runtime::{Map, MapInner, MapValueType},
+    AllocError, LengthMismatch, ProtoStr,
 };
+use std::collections::BTreeMap;
 
 #[derive(Clone, Copy)]
 #[repr(transparent)]
@@ -40,6 +42,12 @@ impl<'a, K: ?Sized, V: ?Sized> MapMut<'a, K, V> {
     pub fn from_inner(_private: Private, inner: MapInner<'a>) -> Self {
         Self { inner: Map::<'a, K, V>::from_inner(_private, inner) }
     }
+
+    /// Rehashes the entries into the smallest table that holds them, e.g.
+    /// after removing most of them.
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit()
+    }
 }
 
 macro_rules! impl_scalar_map_keys {
@@ -49,6 +57,45 @@ macro_rules! impl_scalar_map_keys {
           pub fn get(&self, key: $type) -> Option<V> {
             self.inner.get(key)
           }
+
+          pub fn contains_key(&self, key: $type) -> bool {
+            self.inner.contains_key(key)
+          }
+
+          /// Returns whether every key in `keys` is present.
+          pub fn contains_all(&self, keys: &[$type]) -> bool {
+            keys.iter().all(|&key| self.contains_key(key))
+          }
+
+          /// Looks up each key in `keys`, returning the values in the same order
+          /// with `None` for keys that are absent.
+          pub fn get_many(&self, keys: &[$type]) -> Vec<Option<V>> {
+            keys.iter().map(|&key| self.get(key)).collect()
+          }
+
+          /// Returns the keys in `keys` that are absent, in the order given.
+          pub fn missing_keys(&self, keys: &[$type]) -> Vec<$type> {
+            keys.iter().copied().filter(|&key| !self.contains_key(key)).collect()
+          }
+
+          /// Returns an iterator over the entries, in no particular order.
+          pub fn iter(&self) -> impl Iterator<Item = ($type, V)> + '_ {
+            self.inner.iter()
+          }
+
+          /// Copies the entries into a `BTreeMap`, which iterates in key order.
+          pub fn to_btree_map(&self) -> BTreeMap<$type, V> {
+            self.inner.to_btree_map()
+          }
+
+          /// Returns the value for `key`, or `default` if it is absent.
+          pub fn get_or(&self, key: $type, default: V) -> V {
+            self.inner.get_or(key, default)
+          }
+
+          pub fn count_matching<F: FnMut($type, &V) -> bool>(&self, f: F) -> usize {
+            self.inner.count_matching(f)
+          }
         }
 
         impl<'a, V: MapValueType> MapMut<'a, $type, V> {
@@ -56,6 +103,12 @@ macro_rules! impl_scalar_map_keys {
             self.inner.insert(key, value)
           }
 
+          /// Like `insert`, but returns an error if the map's arena cannot
+          /// allocate, and whether `key` was newly inserted otherwise.
+          pub fn try_insert(&mut self, key: $type, value: V) -> Result<bool, AllocError> {
+            self.inner.try_insert(key, value)
+          }
+
           pub fn remove(&mut self, key: $type) -> Option<V> {
             self.inner.remove(key)
           }
@@ -63,6 +116,24 @@ macro_rules! impl_scalar_map_keys {
           pub fn clear(&mut self) {
             self.inner.clear()
           }
+
+          /// Inserts `keys[i] => values[i]` for every `i`. Inserts nothing if the
+          /// slices have different lengths.
+          pub fn insert_from_slices(
+            &mut self,
+            keys: &[$type],
+            values: &[V],
+          ) -> Result<(), LengthMismatch>
+          where
+            V: Copy,
+          {
+            self.inner.insert_from_slices(keys, values)
+          }
+
+          /// Inserts every entry of `other`, overwriting on key collision.
+          pub fn merge_from(&mut self, other: MapView<'_, $type, V>) {
+            self.inner.merge_from(&other.inner)
+          }
         }
       )*
   };
@@ -75,3 +146,24 @@ impl_scalar_map_keys!(
   key_type u64;
   key_type bool;
 );
+
+impl<'a, V: MapValueType> MapView<'a, ProtoStr, V> {
+    /// Looks up `key` without allocating.
+    pub fn get(&self, key: &str) -> Option<V> {
+        self.inner.get(key)
+    }
+}
+
+impl<'a, V: MapValueType> MapMut<'a, ProtoStr, V> {
+    pub fn insert(&mut self, key: &str, value: V) -> bool {
+        self.inner.insert(key, value)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        self.inner.remove(key)
+    }
+
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+}
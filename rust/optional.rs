@@ -56,6 +56,40 @@ impl<T> Optional<T> {
     pub fn new(val: T, is_set: bool) -> Self {
         if is_set { Optional::Set(val) } else { Optional::Unset(val) }
     }
+
+    /// Returns `self` if it is unset or `pred` accepts the set value, and
+    /// otherwise demotes it to `Unset`, keeping the value.
+    pub fn filter<F: FnOnce(&T) -> bool>(self, pred: F) -> Self {
+        match self {
+            Optional::Set(x) if !pred(&x) => Optional::Unset(x),
+            other => other,
+        }
+    }
+
+    /// Pairs the values of `self` and `other`. The pair is `Set` only if
+    /// both are set.
+    pub fn zip<U>(self, other: Optional<U>) -> Optional<(T, U)> {
+        let is_set = self.is_set() && other.is_set();
+        Optional::new((self.into_inner(), other.into_inner()), is_set)
+    }
+
+    /// Calls `f` with the value if it is set. An unset value short-circuits
+    /// to `Unset(U::default())` without calling `f`.
+    pub fn and_then<U: Default, F: FnOnce(T) -> Optional<U>>(self, f: F) -> Optional<U> {
+        match self {
+            Optional::Set(x) => f(x),
+            Optional::Unset(_) => Optional::Unset(U::default()),
+        }
+    }
+
+    /// Calls `f` with the field value, set or not, and returns `self`
+    /// unchanged. Useful for logging in the middle of a chain of calls.
+    pub fn inspect<F: FnOnce(&T)>(self, f: F) -> Self {
+        match &self {
+            Optional::Set(x) | Optional::Unset(x) => f(x),
+        }
+        self
+    }
 }
 
 impl<T, A> Optional<T, A> {
@@ -73,6 +107,15 @@ impl<T, A> Optional<T, A> {
     pub fn is_unset(&self) -> bool {
         matches!(self, Optional::Unset(_))
     }
+
+    /// Returns if the field is set to `val`. An unset field contains nothing,
+    /// not even its default value.
+    pub fn contains(&self, val: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        matches!(self, Optional::Set(x) if x == val)
+    }
 }
 
 impl<T> From<Optional<T>> for Option<T> {
@@ -724,4 +767,55 @@ mod tests {
         }
         assert_eq!(m.b_opt(), Optional::Set(VtableProxiedView { val: 5 }));
     }
+
+    #[test]
+    fn test_into_option() {
+        assert_eq!(Option::from(Optional::Set(5)), Some(5));
+        assert_eq!(Option::from(Optional::Unset(41)), None::<i32>);
+        let unset: Option<i32> = Optional::Unset(41).into();
+        assert_eq!(unset, None);
+    }
+
+    #[test]
+    fn test_filter() {
+        assert_eq!(Optional::Set(5).filter(|&x| x > 10), Optional::Unset(5));
+        assert_eq!(Optional::Set(15).filter(|&x| x > 10), Optional::Set(15));
+        assert_eq!(Optional::Unset(15).filter(|_| unreachable!()), Optional::Unset(15));
+    }
+
+    #[test]
+    fn test_zip() {
+        assert_eq!(Optional::Set(1).zip(Optional::Unset("b")), Optional::Unset((1, "b")));
+        assert_eq!(Optional::Unset(1).zip(Optional::Set("b")), Optional::Unset((1, "b")));
+        assert_eq!(Optional::Set(1).zip(Optional::Set("b")), Optional::Set((1, "b")));
+    }
+
+    #[test]
+    fn test_and_then() {
+        let half = |x: i32| Optional::new(x / 2, x % 2 == 0);
+        assert_eq!(Optional::Set(8).and_then(half).and_then(half), Optional::Set(2));
+        assert_eq!(Optional::Set(6).and_then(half).and_then(half), Optional::Unset(1));
+        assert_eq!(
+            Optional::Unset(8).and_then(|_| -> Optional<i32> { unreachable!() }),
+            Optional::Unset(0)
+        );
+    }
+
+    #[test]
+    fn test_inspect() {
+        let mut seen = Vec::new();
+        assert_eq!(Optional::Set(5).inspect(|&x| seen.push(x)), Optional::Set(5));
+        assert_eq!(Optional::Unset(41).inspect(|&x| seen.push(x)), Optional::Unset(41));
+        assert_eq!(seen, [5, 41]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let set = Optional::<i32>::Set(5);
+        assert!(set.contains(&5));
+        assert!(!set.contains(&6));
+        let unset = Optional::<i32>::Unset(41);
+        assert!(!unset.contains(&41));
+        assert!(!unset.contains(&6));
+    }
 }
@@ -0,0 +1,64 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use crate::__internal::Private;
+
+/// The numbers of the fields that are present on a message, as returned by
+/// the generated `present_fields()` accessor.
+///
+/// Only fields with explicit presence are tracked; repeated, map, and
+/// implicit-presence fields are never included. Neither are enum, group, and
+/// `[ctype]` fields, or message fields whose type is defined in another crate,
+/// since those have no generated accessors yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PresenceSet {
+    // Sorted in ascending order with no duplicates.
+    numbers: Vec<u32>,
+}
+
+impl PresenceSet {
+    #[doc(hidden)]
+    pub fn from_numbers(_private: Private, mut numbers: Vec<u32>) -> Self {
+        numbers.sort_unstable();
+        numbers.dedup();
+        Self { numbers }
+    }
+
+    /// Returns whether the field with the given number is present.
+    pub fn contains(&self, number: u32) -> bool {
+        self.numbers.binary_search(&number).is_ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.numbers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.numbers.is_empty()
+    }
+
+    /// Returns the present field numbers in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.numbers.iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presence_set() {
+        let set = PresenceSet::from_numbers(Private, vec![15, 1, 15]);
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(1));
+        assert!(set.contains(15));
+        assert!(!set.contains(2));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![1, 15]);
+        assert!(PresenceSet::default().is_empty());
+    }
+}
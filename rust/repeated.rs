@@ -14,7 +14,7 @@ use std::marker::PhantomData;
 use crate::{
     Mut, MutProxy, Proxied, SettableValue, View, ViewProxy,
     __internal::{Private, RawRepeatedField},
-    __runtime::{RepeatedField, RepeatedFieldInner},
+    __runtime::{OwnedRepeatedField, RepeatedField, RepeatedFieldInner},
     primitive::PrimitiveMut,
     vtable::ProxiedWithRawVTable,
 };
@@ -43,9 +43,14 @@ impl<'msg, T: ?Sized> RepeatedView<'msg, T> {
     }
 }
 
+// The iterators cache `len()` on creation so that `next()` can stop at the
+// end without asking the kernel for it (an FFI call). Element reads stay
+// bounds-checked: the kernel `RepeatedField` is `Copy`, so a copy of it may
+// shrink the field while an iterator is alive, which just ends the iteration.
 pub struct RepeatedFieldIter<'a, T> {
     inner: RepeatedField<'a, T>,
     current_index: usize,
+    len: usize,
 }
 
 impl<'a, T> std::fmt::Debug for RepeatedView<'a, T> {
@@ -85,10 +90,41 @@ impl<'a, T> std::ops::Deref for RepeatedMut<'a, T> {
 pub struct RepeatedFieldIterMut<'a, T> {
     inner: RepeatedMut<'a, T>,
     current_index: usize,
+    len: usize,
 }
 
 pub struct Repeated<T>(PhantomData<T>);
 
+/// A repeated field that is not attached to any message.
+#[derive(Debug)]
+pub struct OwnedRepeated<T: ?Sized> {
+    inner: OwnedRepeatedField<T>,
+}
+
+impl<T: ?Sized> OwnedRepeated<T> {
+    pub fn as_view(&self) -> RepeatedView<'_, T> {
+        RepeatedView::from_inner(Private, self.inner.as_inner())
+    }
+    pub fn as_mut(&mut self) -> RepeatedMut<'_, T> {
+        RepeatedMut::from_inner(Private, self.inner.as_inner())
+    }
+}
+
+/// Stably partitions `slice` by `pred`, calling it once per element in order,
+/// and returns the number of matching elements. Each half is partitioned
+/// recursively, then the non-matching tail of the left half is rotated past
+/// the matching head of the right half.
+fn stable_partition<T, F: FnMut(&T) -> bool>(slice: &mut [T], pred: &mut F) -> usize {
+    if slice.len() <= 1 {
+        return slice.first().map_or(0, |val| pred(val) as usize);
+    }
+    let mid = slice.len() / 2;
+    let left = stable_partition(&mut slice[..mid], pred);
+    let right = stable_partition(&mut slice[mid..], pred);
+    slice[left..mid + right].rotate_left(mid - left);
+    left + right
+}
+
 macro_rules! impl_repeated_primitives {
     ($($t:ty),*) => {
         $(
@@ -153,15 +189,157 @@ macro_rules! impl_repeated_primitives {
                 pub fn get(&self, index: usize) -> Option<$t> {
                     self.inner.get(index)
                 }
+                /// Returns the element at `index`, or the default value of the element
+                /// type if `index` is out of range.
+                pub fn get_or_default(&self, index: usize) -> $t {
+                    self.get(index).unwrap_or_default()
+                }
+                /// Returns an iterator over the elements, by value.
                 pub fn iter(&self) -> RepeatedFieldIter<'_, $t> {
                     (*self).into_iter()
                 }
+                /// Same as [`iter`](Self::iter), which already yields elements by
+                /// value; provided for callers used to `slice.iter().copied()`.
+                pub fn copied(&self) -> RepeatedFieldIter<'_, $t> {
+                    self.iter()
+                }
+                /// Returns the elements as a contiguous slice without copying.
+                pub fn as_slice(&self) -> &[$t] {
+                    self.inner.as_slice()
+                }
+                /// Returns the elements in `range`, or `None` if it is out of bounds.
+                pub fn get_range(&self, range: std::ops::Range<usize>) -> Option<&[$t]> {
+                    self.as_slice().get(range)
+                }
+                /// Copies the elements into an array, or returns `None` unless there are
+                /// exactly `N` of them.
+                pub fn to_array<const N: usize>(&self) -> Option<[$t; N]> {
+                    self.as_slice().try_into().ok()
+                }
+                /// Returns whether the elements are in non-decreasing order. Any `NaN`
+                /// makes a float field unsorted.
+                pub fn is_sorted(&self) -> bool {
+                    self.as_slice().windows(2).all(|w| w[0] <= w[1])
+                }
+                /// Folds every element into an accumulator, walking the underlying buffer
+                /// directly rather than going through the per-element iterator.
+                pub fn fold<B, F: FnMut(B, $t) -> B>(&self, init: B, f: F) -> B {
+                    self.as_slice().iter().copied().fold(init, f)
+                }
+                /// Reduces the elements to a single one by repeatedly applying `f`, or
+                /// returns `None` if the field is empty.
+                pub fn reduce<F: FnMut($t, $t) -> $t>(&self, f: F) -> Option<$t> {
+                    self.as_slice().iter().copied().reduce(f)
+                }
+                /// Returns the number of elements the field can hold without reallocating.
+                pub fn capacity(&self) -> usize {
+                    self.inner.capacity()
+                }
+                /// Returns an iterator over every `step`-th element, starting with the first.
+                ///
+                /// # Panics
+                /// Panics if `step` is 0.
+                pub fn stride(&self, step: usize) -> impl Iterator<Item = $t> + '_ {
+                    self.as_slice().iter().step_by(step).copied()
+                }
+                /// Returns an iterator over each element paired with the one after it,
+                /// which is `None` for the last element.
+                pub fn peeking_iter(&self) -> impl Iterator<Item = ($t, Option<$t>)> + '_ {
+                    let slice = self.as_slice();
+                    slice.iter().enumerate().map(move |(i, &val)| (val, slice.get(i + 1).copied()))
+                }
+                /// Returns an iterator over all contiguous windows of length `size`, like
+                /// [`slice::windows`].
+                ///
+                /// # Panics
+                /// Panics if `size` is 0.
+                pub fn windows(&self, size: usize) -> impl Iterator<Item = &[$t]> + '_ {
+                    self.as_slice().windows(size)
+                }
+            }
+
+            impl<'a, 'b> PartialEq<RepeatedView<'b, $t>> for RepeatedView<'a, $t> {
+                /// Compares the lengths and then the elements, in order.
+                fn eq(&self, other: &RepeatedView<'b, $t>) -> bool {
+                    self.as_slice() == other.as_slice()
+                }
             }
 
             impl<'a> RepeatedMut<'a, $t> {
                 pub fn push(&mut self, val: $t) {
                     self.inner.push(val)
                 }
+                /// Appends all of `src`, which is faster than pushing each element.
+                pub fn extend_from_slice(&mut self, src: &[$t]) {
+                    self.inner.extend_from_slice(src)
+                }
+                /// Pushes `val` and returns its index, which is the previous `len()`.
+                pub fn push_indexed(&mut self, val: $t) -> usize {
+                    let index = self.len();
+                    self.push(val);
+                    index
+                }
+                /// Reserves capacity for at least `additional` more elements.
+                pub fn reserve(&mut self, additional: usize) {
+                    self.inner.reserve(additional)
+                }
+                /// Shrinks the capacity as close to `len()` as the runtime allows.
+                ///
+                /// Arena memory is only released when the arena is destroyed, so with
+                /// the C++ kernel this is a no-op for fields allocated on an arena. With
+                /// upb, memory is only given back if the field's buffer was the arena's
+                /// most recent allocation.
+                pub fn shrink_to_fit(&mut self) {
+                    self.inner.shrink_to_fit()
+                }
+                /// Removes all elements. The capacity is kept so the field can be refilled
+                /// without reallocating.
+                pub fn clear(&mut self) {
+                    self.inner.clear()
+                }
+                /// Keeps the first `len` elements and drops the rest. Does nothing if
+                /// `len` is not less than `self.len()`.
+                pub fn truncate(&mut self, len: usize) {
+                    self.inner.truncate(len)
+                }
+                /// Sorts the elements in place with `compare`. Equal elements may be
+                /// reordered.
+                pub fn sort_by<F>(&mut self, compare: F)
+                where
+                    F: FnMut(&$t, &$t) -> std::cmp::Ordering,
+                {
+                    self.inner.sort_by(compare)
+                }
+                /// Keeps only the elements for which `f` returns `true`, preserving their
+                /// order.
+                pub fn retain<F: FnMut(&$t) -> bool>(&mut self, f: F) {
+                    self.inner.retain(f)
+                }
+                /// Replaces the elements in `range` with `replace_with`, like
+                /// `Vec::splice`.
+                ///
+                /// # Panics
+                /// Panics if `range` is decreasing or out of bounds.
+                pub fn splice(&mut self, range: std::ops::Range<usize>, replace_with: &[$t]) {
+                    self.inner.splice(range, replace_with)
+                }
+                /// Inserts `val` at `index`, shifting all elements after it to the right.
+                ///
+                /// # Panics
+                /// Panics if `index > len()`.
+                pub fn insert(&mut self, index: usize, val: $t) {
+                    self.inner.insert(index, val)
+                }
+                /// Removes the element at `index`, keeping the order of the remaining
+                /// elements. Returns `None` if `index` is out of bounds.
+                pub fn remove(&mut self, index: usize) -> Option<$t> {
+                    self.inner.remove(index)
+                }
+                /// Removes the element at `index` in O(1) by moving the last element into
+                /// its place. Returns `None` if `index` is out of bounds.
+                pub fn swap_remove(&mut self, index: usize) -> Option<$t> {
+                    self.inner.swap_remove(index)
+                }
                 pub fn set(&mut self, index: usize, val: $t) {
                     self.inner.set(index, val)
                 }
@@ -177,19 +355,94 @@ macro_rules! impl_repeated_primitives {
                 pub fn iter_mut(&mut self) -> RepeatedFieldIterMut<'_, $t> {
                     self.as_mut().into_iter()
                 }
+                /// Replaces the contents with a copy of `src`, which may belong to
+                /// another message.
                 pub fn copy_from(&mut self, src: RepeatedView<'_, $t>) {
                     self.inner.copy_from(&src.inner);
                 }
+                /// Returns an iterator over mutable, non-overlapping chunks of `size`
+                /// elements. The last chunk is shorter if `size` does not divide `len()`.
+                ///
+                /// # Panics
+                /// Panics if `size` is 0.
+                pub fn chunks_mut(
+                    &mut self,
+                    size: usize,
+                ) -> impl Iterator<Item = &mut [$t]> + '_ {
+                    self.inner.as_mut_slice().chunks_mut(size)
+                }
+                /// Overwrites every element with the corresponding element of `src`.
+                ///
+                /// # Panics
+                /// Panics if `src.len() != self.len()`.
+                pub fn clone_from_slice(&mut self, src: &[$t]) {
+                    self.inner.clone_from_slice(src);
+                }
+                /// Reverses the order of the elements in place.
+                pub fn reverse(&mut self) {
+                    self.inner.as_mut_slice().reverse();
+                }
+                /// Moves the elements for which `pred` returns `true` to the front,
+                /// keeping the relative order within both groups, and returns how many
+                /// matched. Does not allocate; takes O(n log n) moves.
+                pub fn partition_in_place<F: FnMut(&$t) -> bool>(&mut self, mut pred: F) -> usize {
+                    stable_partition(self.inner.as_mut_slice(), &mut pred)
+                }
+            }
+
+            impl OwnedRepeated<$t> {
+                pub fn new() -> Self {
+                    Self { inner: OwnedRepeatedField::<$t>::new() }
+                }
+                /// Creates an empty field with room for at least `capacity` elements.
+                pub fn with_capacity(capacity: usize) -> Self {
+                    let mut repeated = Self::new();
+                    repeated.as_mut().reserve(capacity);
+                    repeated
+                }
+            }
+
+            impl Default for OwnedRepeated<$t> {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            impl<const N: usize> From<[$t; N]> for OwnedRepeated<$t> {
+                fn from(vals: [$t; N]) -> Self {
+                    let mut repeated = Self::new();
+                    repeated.as_mut().extend(vals);
+                    repeated
+                }
+            }
+
+            impl<'msg> std::iter::Extend<$t> for RepeatedField<'msg, $t> {
+                fn extend<I: IntoIterator<Item = $t>>(&mut self, iter: I) {
+                    for val in iter {
+                        self.push(val);
+                    }
+                }
+            }
+
+            impl<'a> std::iter::Extend<$t> for RepeatedMut<'a, $t> {
+                fn extend<I: IntoIterator<Item = $t>>(&mut self, iter: I) {
+                    self.inner.extend(iter)
+                }
             }
 
             impl<'a> std::iter::Iterator for RepeatedFieldIter<'a, $t> {
                 type Item = $t;
                 fn next(&mut self) -> Option<Self::Item> {
-                    let val = self.inner.get(self.current_index);
-                    if val.is_some() {
-                        self.current_index += 1;
+                    if self.current_index >= self.len {
+                        return None;
                     }
-                    val
+                    let val = self.inner.get(self.current_index)?;
+                    self.current_index += 1;
+                    Some(val)
+                }
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    let remaining = self.len.saturating_sub(self.current_index);
+                    (remaining, Some(remaining))
                 }
             }
 
@@ -197,14 +450,30 @@ macro_rules! impl_repeated_primitives {
                 type Item = $t;
                 type IntoIter = RepeatedFieldIter<'a, $t>;
                 fn into_iter(self) -> Self::IntoIter {
-                    RepeatedFieldIter { inner: self.inner, current_index: 0 }
+                    RepeatedFieldIter { inner: self.inner, current_index: 0, len: self.len() }
+                }
+            }
+
+            impl<'a, 'b> std::iter::IntoIterator for &'b RepeatedView<'a, $t> {
+                type Item = $t;
+                type IntoIter = RepeatedFieldIter<'b, $t>;
+                fn into_iter(self) -> Self::IntoIter {
+                    self.iter()
+                }
+            }
+
+            impl<'a, 'b> std::iter::IntoIterator for &'b RepeatedField<'a, $t> {
+                type Item = $t;
+                type IntoIter = RepeatedFieldIter<'b, $t>;
+                fn into_iter(self) -> Self::IntoIter {
+                    RepeatedFieldIter { inner: *self, current_index: 0, len: self.len() }
                 }
             }
 
             impl <'a> std::iter::Iterator for RepeatedFieldIterMut<'a, $t> {
                 type Item = Mut<'a, $t>;
                 fn next(&mut self) -> Option<Self::Item> {
-                    if self.current_index >= self.inner.len() {
+                    if self.current_index >= self.len {
                         return None;
                     }
                     let elem = PrimitiveMut::Repeated(
@@ -223,7 +492,8 @@ macro_rules! impl_repeated_primitives {
                 type Item = Mut<'a, $t>;
                 type IntoIter = RepeatedFieldIterMut<'a, $t>;
                 fn into_iter(self) -> Self::IntoIter {
-                    RepeatedFieldIterMut { inner: self, current_index: 0 }
+                    let len = self.len();
+                    RepeatedFieldIterMut { inner: self, current_index: 0, len }
                 }
             }
         )*
@@ -231,3 +501,73 @@ macro_rules! impl_repeated_primitives {
 }
 
 impl_repeated_primitives!(i32, u32, bool, f32, f64, i64, u64);
+
+macro_rules! impl_repeated_numeric {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> RepeatedView<'a, $t> {
+                /// Returns the number of elements that are not zero. For floats, both
+                /// `0.0` and `-0.0` count as zero.
+                pub fn count_nonzero(&self) -> usize {
+                    self.as_slice().iter().filter(|&&val| val != 0 as $t).count()
+                }
+                /// Returns an iterator over the elements converted to a wider type,
+                /// e.g. to sum a `repeated int32` as `i64` without overflowing.
+                pub fn iter_as<U: From<$t>>(&self) -> impl Iterator<Item = U> + '_ {
+                    self.as_slice().iter().map(|&val| U::from(val))
+                }
+            }
+        )*
+    }
+}
+
+impl_repeated_numeric!(i32, u32, i64, u64, f32, f64);
+
+macro_rules! impl_repeated_arithmetic {
+    ($($t:ty: $add:expr, $mul:expr;)*) => {
+        $(
+            impl<'a> RepeatedMut<'a, $t> {
+                /// Adds `x` to every element in place.
+                ///
+                /// Integer overflow wraps around, as with `wrapping_add`.
+                pub fn add_scalar(&mut self, x: $t) {
+                    for val in self.inner.as_mut_slice() {
+                        *val = $add(*val, x);
+                    }
+                }
+                /// Multiplies every element by `x` in place.
+                ///
+                /// Integer overflow wraps around, as with `wrapping_mul`.
+                pub fn mul_scalar(&mut self, x: $t) {
+                    for val in self.inner.as_mut_slice() {
+                        *val = $mul(*val, x);
+                    }
+                }
+            }
+        )*
+    }
+}
+
+macro_rules! impl_repeated_ord {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> RepeatedMut<'a, $t> {
+                /// Sorts the elements in place. Equal elements may be reordered.
+                pub fn sort(&mut self) {
+                    self.inner.sort()
+                }
+            }
+        )*
+    }
+}
+
+impl_repeated_ord!(bool, i32, u32, i64, u64);
+
+impl_repeated_arithmetic! {
+    i32: i32::wrapping_add, i32::wrapping_mul;
+    u32: u32::wrapping_add, u32::wrapping_mul;
+    i64: i64::wrapping_add, i64::wrapping_mul;
+    u64: u64::wrapping_add, u64::wrapping_mul;
+    f32: std::ops::Add::add, std::ops::Mul::mul;
+    f64: std::ops::Add::add, std::ops::Mul::mul;
+}
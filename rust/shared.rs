@@ -17,15 +17,26 @@ use std::fmt;
 /// These are the items protobuf users can access directly.
 #[doc(hidden)]
 pub mod __public {
+    pub use crate::cached::CachedMessage;
+    #[cfg(upb_kernel)]
+    pub use crate::descriptor::{DefPool, DescriptorError, MessageDescriptor};
+    #[cfg(upb_kernel)]
+    pub use crate::dynamic::{
+        transcode_wire_to_json, DynamicMessage, DynamicValue, FieldError, TranscodeError,
+    };
+    pub use crate::error::Error;
+    pub use crate::field_info::{FieldInfo, FieldKind};
     #[cfg(upb_kernel)]
     pub use crate::map::{MapMut, MapView};
     pub use crate::optional::{AbsentField, FieldEntry, Optional, PresentField};
+    pub use crate::presence::PresenceSet;
     pub use crate::primitive::{PrimitiveMut, SingularPrimitiveMut};
     pub use crate::proxied::{
         Mut, MutProxy, Proxied, ProxiedWithPresence, SettableValue, View, ViewProxy,
     };
-    pub use crate::repeated::{RepeatedFieldRef, RepeatedMut, RepeatedView};
+    pub use crate::repeated::{OwnedRepeated, RepeatedFieldRef, RepeatedMut, RepeatedView};
     pub use crate::string::{BytesMut, ProtoStr, ProtoStrMut};
+    pub use crate::wire::WireType;
 }
 pub use __public::*;
 
@@ -45,15 +56,25 @@ pub mod __runtime;
 #[path = "upb.rs"]
 pub mod __runtime;
 
+mod cached;
+mod delimited;
+#[cfg(upb_kernel)]
+mod descriptor;
+#[cfg(upb_kernel)]
+mod dynamic;
+mod error;
+mod field_info;
 mod macros;
 #[cfg(upb_kernel)]
 mod map;
 mod optional;
+mod presence;
 mod primitive;
 mod proxied;
 mod repeated;
 mod string;
 mod vtable;
+mod wire;
 
 /// An error that happened during deserialization.
 #[derive(Debug, Clone)]
@@ -64,3 +85,30 @@ impl fmt::Display for ParseError {
         write!(f, "Couldn't deserialize given bytes into a proto")
     }
 }
+
+impl std::error::Error for ParseError {}
+
+/// An error that happened because memory could not be allocated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Couldn't allocate memory")
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+/// An error that happened because two inputs that must be the same length
+/// were not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthMismatch;
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Inputs have different lengths")
+    }
+}
+
+impl std::error::Error for LengthMismatch {}
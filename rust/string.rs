@@ -72,6 +72,19 @@ impl<'msg> BytesMut<'msg> {
         val.set_on(Private, MutProxy::as_mut(self))
     }
 
+    /// Appends `more` to the end of the byte string.
+    ///
+    /// Each call copies the whole concatenated value into a new allocation (in
+    /// the message's arena for upb), so appending many small pieces this way is
+    /// quadratic. Build the value in a `Vec<u8>` and `set` it instead.
+    pub fn append(&mut self, more: &[u8]) {
+        if more.is_empty() {
+            return;
+        }
+        // SAFETY: this is a `bytes` field with no restriction on UTF-8.
+        unsafe { self.inner.append(more) }
+    }
+
     /// Truncates the byte string.
     ///
     /// Has no effect if `new_len` is larger than the current `len`.
@@ -21,6 +21,12 @@ macro_rules! generate_map_primitives_tests {
                 let v: $v_type = Default::default();
                 assert_that!(msg.[< map_ $k_field _ $v_field _mut>]().insert(k, v), eq(true));
                 assert_that!(msg.[< map_ $k_field _ $v_field >]().len(), eq(1));
+
+                // The read accessor is a `MapView`, which only exposes reads.
+                let view = msg.[< map_ $k_field _ $v_field >]();
+                assert_that!(view.get(k), eq(Some(v)));
+                assert_that!(view.contains_key(k), eq(true));
+                assert_that!(view.iter().collect::<Vec<_>>(), eq(vec![(k, v)]));
             }
         )* }
     };
@@ -41,3 +47,37 @@ generate_map_primitives_tests!(
     (i32, f64, int32, double),
     (bool, bool, bool, bool)
 );
+
+#[test]
+fn test_map_contains_all_and_missing_keys() {
+    let mut msg = TestMap::new();
+    for k in [1, 2, 3] {
+        msg.map_int32_int32_mut().insert(k, k * 10);
+    }
+    let view = msg.map_int32_int32();
+    assert_that!(view.contains_all(&[1, 2]), eq(true));
+    assert_that!(view.contains_all(&[2, 5]), eq(false));
+    assert_that!(view.contains_all(&[]), eq(true));
+    assert_that!(view.missing_keys(&[2, 5]), eq(vec![5]));
+}
+
+#[test]
+fn test_map_canonical_bytes() {
+    let mut msg1 = TestMap::new();
+    let mut msg2 = TestMap::new();
+    for k in 0..100 {
+        msg1.map_int32_int32_mut().insert(k, k * 10);
+        msg2.map_int32_int32_mut().insert(99 - k, (99 - k) * 10);
+    }
+    assert_that!(*msg1.canonical_bytes(), eq(*msg2.canonical_bytes()));
+}
+
+#[test]
+fn test_map_get_many() {
+    let mut msg = TestMap::new();
+    msg.map_int32_int32_mut().insert(1, 10);
+    msg.map_int32_int32_mut().insert(2, 20);
+    let view = msg.map_int32_int32();
+    assert_that!(view.get_many(&[2, 5, 1]), eq(vec![Some(20), None, Some(10)]));
+    assert_that!(view.get_many(&[]), eq(vec![]));
+}
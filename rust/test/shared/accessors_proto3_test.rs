@@ -239,3 +239,27 @@ fn test_oneof_mut_accessors() {
     msg.oneof_bytes_mut().set(b"123");
     assert_that!(msg.oneof_field_mut(), matches_pattern!(OneofBytes(_)));
 }
+
+#[test]
+fn test_optional_vs_implicit_presence() {
+    // `optional int32` in proto3 has explicit presence: setting the default
+    // value is still observable.
+    let mut optional = TestProto3Optional::new();
+    assert_that!(optional.optional_int32_opt(), eq(Optional::Unset(0)));
+    optional.optional_int32_set(Some(0));
+    assert_that!(optional.optional_int32_opt(), eq(Optional::Set(0)));
+    assert_that!(optional.optional_int32_mut().is_set(), eq(true));
+    assert_that!(*optional.serialize(), not(empty()));
+    optional.optional_int32_set(None);
+    assert_that!(optional.optional_int32_opt(), eq(Optional::Unset(0)));
+    assert_that!(optional.optional_int32_mut().is_set(), eq(false));
+
+    // A bare proto3 `int32` has implicit presence: there is no `_opt()` or
+    // hazzer, and setting the default value is indistinguishable from unset.
+    let mut implicit = TestAllTypes::new();
+    implicit.optional_int32_mut().set(0);
+    assert_that!(implicit.optional_int32(), eq(0));
+    assert_that!(*implicit.serialize(), empty());
+    implicit.optional_int32_mut().set(1);
+    assert_that!(*implicit.serialize(), not(empty()));
+}
@@ -8,9 +8,9 @@
 //! Tests covering accessors for singular bool, int32, int64, and bytes fields.
 
 use googletest::prelude::*;
-use matchers::{is_set, is_unset};
+use matchers::{eq_elements, is_set, is_unset};
 use paste::paste;
-use protobuf::Optional;
+use protobuf::{FieldKind, Optional};
 use unittest_proto::proto2_unittest::{TestAllTypes, TestAllTypes_};
 
 #[test]
@@ -541,6 +541,16 @@ fn test_optional_bytes_accessors() {
     assert_that!(msg.optional_bytes_mut().or_default().get(), eq(b"\xffbinary\x85non-utf8"));
 }
 
+#[test]
+fn test_bytes_append() {
+    let mut msg = TestAllTypes::new();
+    msg.optional_bytes_mut().set(b"foo");
+    msg.optional_bytes_mut().or_default().append(b"bar");
+    assert_that!(msg.optional_bytes(), eq(b"foobar"));
+    msg.optional_bytes_mut().or_default().append(b"");
+    assert_that!(msg.optional_bytes(), eq(b"foobar"));
+}
+
 #[test]
 fn test_nonempty_default_bytes_accessors() {
     let mut msg = TestAllTypes::new();
@@ -730,6 +740,97 @@ fn test_oneof_mut_accessors() {
     assert_that!(msg.oneof_field_mut(), matches_pattern!(OneofBytes(_)));
 }
 
+#[test]
+fn test_present_fields() {
+    let mut msg = TestAllTypes::new();
+    assert_that!(msg.present_fields().is_empty(), eq(true));
+
+    msg.optional_int32_set(Some(1));
+    msg.optional_bytes_mut().set(b"abc");
+    let present = msg.present_fields();
+    assert_that!(present.iter().collect::<Vec<_>>(), eq(vec![1, 15]));
+    assert_that!(present.contains(1), eq(true));
+    assert_that!(present.contains(14), eq(false));
+}
+
+#[test]
+fn test_present_fields_sub_message() {
+    let mut msg = TestAllTypes::new();
+    // Set `optional_nested_message` (field 18) to an empty submessage.
+    assert!(msg.deserialize(&[0x92, 0x01, 0x00]).is_ok());
+    assert_that!(msg.present_fields().iter().collect::<Vec<_>>(), eq(vec![18]));
+}
+
+#[test]
+fn test_oneof_clear_by_name() {
+    let mut msg = TestAllTypes::new();
+    msg.oneof_uint32_set(Some(7));
+    assert_that!(msg.clear_oneof_by_name("not_a_oneof"), eq(false));
+    assert_that!(msg.oneof_uint32_opt(), eq(Optional::Set(7)));
+
+    assert_that!(msg.clear_oneof_by_name("oneof_field"), eq(true));
+    assert_that!(msg.oneof_uint32_opt(), eq(Optional::Unset(0)));
+    assert_that!(msg.oneof_field(), matches_pattern!(TestAllTypes_::OneofField::not_set(_)));
+
+    // Clearing an already-unset oneof is still a known name.
+    assert_that!(msg.clear_oneof_by_name("oneof_field"), eq(true));
+}
+
+#[test]
+fn test_oneof_clear_by_name_message_case() {
+    let mut msg = TestAllTypes::new();
+    // Message cases have no setter yet, so set `oneof_nested_message` (field
+    // 112) to an empty submessage through the wire format.
+    assert!(msg.deserialize(&[0x82, 0x07, 0x00]).is_ok());
+    assert_that!(msg.serialize().len(), eq(3));
+
+    assert_that!(msg.clear_oneof_by_name("oneof_field"), eq(true));
+    assert_that!(msg.serialize().len(), eq(0));
+}
+
+#[test]
+fn test_oneof_take() {
+    use TestAllTypes_::OneofFieldOwned;
+
+    let mut msg = TestAllTypes::new();
+    assert_that!(msg.take_oneof_field(), eq(OneofFieldOwned::not_set));
+
+    msg.oneof_bytes_mut().set(b"123");
+    assert_that!(msg.take_oneof_field(), eq(OneofFieldOwned::OneofBytes(b"123".to_vec())));
+    assert_that!(msg.oneof_field(), matches_pattern!(TestAllTypes_::OneofField::not_set(_)));
+    assert_that!(msg.oneof_bytes_mut(), is_unset());
+    assert_that!(msg.take_oneof_field(), eq(OneofFieldOwned::not_set));
+
+    msg.oneof_uint32_set(Some(7));
+    assert_that!(msg.take_oneof_field(), eq(OneofFieldOwned::OneofUint32(7)));
+    assert_that!(msg.oneof_uint32_opt(), eq(Optional::Unset(0)));
+
+    msg.oneof_string_mut().set("abc");
+    assert_that!(msg.take_oneof_field(), eq(OneofFieldOwned::OneofString("abc".to_string())));
+    assert_that!(msg.oneof_string_mut(), is_unset());
+
+    // Message cases cannot be returned yet, so they are left set.
+    assert!(msg.deserialize(&[0x82, 0x07, 0x00]).is_ok());
+    assert_that!(msg.take_oneof_field(), eq(OneofFieldOwned::not_set));
+    assert_that!(msg.serialize().len(), eq(3));
+}
+
+#[test]
+fn test_oneof_view_into_owned() {
+    use TestAllTypes_::OneofFieldOwned;
+
+    let mut msg = TestAllTypes::new();
+    assert_that!(OneofFieldOwned::from(msg.oneof_field()), eq(OneofFieldOwned::not_set));
+
+    msg.oneof_bytes_mut().set(b"snapshot");
+    let snapshot = OneofFieldOwned::from(msg.oneof_field());
+    msg.oneof_bytes_mut().set(b"changed");
+    assert_that!(snapshot, eq(OneofFieldOwned::OneofBytes(b"snapshot".to_vec())));
+
+    msg.oneof_uint32_set(Some(7));
+    assert_that!(OneofFieldOwned::from(msg.oneof_field()), eq(OneofFieldOwned::OneofUint32(7)));
+}
+
 macro_rules! generate_repeated_numeric_test {
     ($(($t: ty, $field: ident)),*) => {
         paste! { $(
@@ -789,6 +890,19 @@ macro_rules! generate_repeated_numeric_test {
                     eq(mutator2.iter().collect::<Vec<_>>())
                 );
             }
+
+            #[test]
+            fn [< test_repeated_ $field _as_slice >]() {
+                let mut msg = TestAllTypes::new();
+                assert_that!(msg.[< repeated_ $field >]().as_slice().is_empty(), eq(true));
+
+                let mut mutator = msg.[<repeated_ $field _mut>]();
+                for i in 0..5 {
+                    mutator.push(i as $t);
+                }
+                let view = msg.[< repeated_ $field >]();
+                assert_that!(view.as_slice().iter().sum::<$t>(), eq(view.iter().sum::<$t>()));
+            }
         )* }
     };
 }
@@ -846,3 +960,395 @@ fn test_repeated_bool_set() {
 
     assert_that!(mutator.iter().collect::<Vec<_>>(), eq(mutator2.iter().collect::<Vec<_>>()));
 }
+
+#[test]
+fn test_repeated_int32_stride() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend(0..10);
+    assert_that!(msg.repeated_int32().stride(3).collect::<Vec<_>>(), eq(vec![0, 3, 6, 9]));
+    assert_that!(msg.repeated_int32().stride(20).collect::<Vec<_>>(), eq(vec![0]));
+}
+
+#[test]
+fn test_owned_repeated_from_array() {
+    let r: protobuf::OwnedRepeated<i32> = [1, 2, 3].into();
+    assert_that!(r.as_view().iter().collect::<Vec<_>>(), eq(vec![1, 2, 3]));
+
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().copy_from(r.as_view());
+    assert_that!(msg.repeated_int32().as_slice(), eq([1, 2, 3]));
+}
+
+#[test]
+fn test_repeated_int32_copy_from_other_message() {
+    let mut src = TestAllTypes::new();
+    src.repeated_int32_mut().extend([4, 5, 6]);
+    let mut dst = TestAllTypes::new();
+    dst.repeated_int32_mut().extend([1]);
+
+    dst.repeated_int32_mut().copy_from(src.repeated_int32());
+    assert_that!(dst.repeated_int32(), eq(src.repeated_int32()));
+}
+
+#[test]
+fn test_repeated_int32_chunks_mut() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend(1..=7);
+    for chunk in mutator.chunks_mut(3) {
+        for val in chunk {
+            *val *= 2;
+        }
+    }
+    assert_that!(msg.repeated_int32().as_slice(), eq([2, 4, 6, 8, 10, 12, 14]));
+}
+
+#[test]
+fn test_repeated_int32_arithmetic() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([1, 2, 3]);
+    mutator.add_scalar(10);
+    assert_that!(mutator.as_slice(), eq([11, 12, 13]));
+    mutator.mul_scalar(2);
+    assert_that!(mutator.as_slice(), eq([22, 24, 26]));
+
+    mutator.clone_from_slice(&[i32::MAX, 0, -1]);
+    mutator.add_scalar(1);
+    assert_that!(mutator.as_slice(), eq([i32::MIN, 1, 0]));
+}
+
+#[test]
+fn test_repeated_int32_eq_elements() {
+    let mut msg1 = TestAllTypes::new();
+    let mut msg2 = TestAllTypes::new();
+    msg1.repeated_int32_mut().extend([1, 2, 3]);
+    msg2.repeated_int32_mut().extend([1, 2, 3]);
+    assert_that!(eq_elements(msg1.repeated_int32(), msg2.repeated_int32()), eq(true));
+    assert_that!(eq_elements(msg1.repeated_int32(), [1, 2, 3]), eq(true));
+
+    msg2.repeated_int32_mut().push(4);
+    assert_that!(eq_elements(msg1.repeated_int32(), msg2.repeated_int32()), eq(false));
+    msg1.repeated_int32_mut().push(5);
+    assert_that!(eq_elements(msg1.repeated_int32(), msg2.repeated_int32()), eq(false));
+}
+
+#[test]
+fn test_repeated_int32_get_or_default() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend([3, 4]);
+    assert_that!(msg.repeated_int32().get_or_default(1), eq(4));
+    assert_that!(msg.repeated_int32().get_or_default(2), eq(0));
+}
+
+#[test]
+fn test_repeated_int32_copied() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend([1, 2, 3]);
+    let vals: Vec<i32> = msg.repeated_int32().copied().collect();
+    assert_that!(vals, eq(vec![1, 2, 3]));
+}
+
+#[test]
+fn test_repeated_int32_is_sorted() {
+    let mut msg = TestAllTypes::new();
+    assert_that!(msg.repeated_int32().is_sorted(), eq(true));
+    msg.repeated_int32_mut().extend([1, 2, 3]);
+    assert_that!(msg.repeated_int32().is_sorted(), eq(true));
+    msg.repeated_int32_mut().clone_from_slice(&[3, 1, 2]);
+    assert_that!(msg.repeated_int32().is_sorted(), eq(false));
+}
+
+#[test]
+fn test_repeated_int32_iter_all() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend(0..1000);
+    let iter = msg.repeated_int32().iter();
+    assert_that!(iter.size_hint(), eq((1000, Some(1000))));
+    assert_that!(iter.collect::<Vec<_>>(), eq((0..1000).collect::<Vec<_>>()));
+
+    for mut val in msg.repeated_int32_mut().iter_mut() {
+        let doubled = val.get() * 2;
+        val.set(doubled);
+    }
+    assert_that!(msg.repeated_int32().iter().last(), eq(Some(1998)));
+}
+
+#[test]
+fn test_repeated_int32_eq() {
+    let mut msg1 = TestAllTypes::new();
+    let mut msg2 = TestAllTypes::new();
+    msg1.repeated_int32_mut().extend([1, 2, 3]);
+    msg2.repeated_int32_mut().extend([1, 2, 3]);
+    assert_that!(msg1.repeated_int32(), eq(msg2.repeated_int32()));
+
+    msg2.repeated_int32_mut().set(2, 4);
+    assert_that!(msg1.repeated_int32(), not(eq(msg2.repeated_int32())));
+    msg2.repeated_int32_mut().push(4);
+    assert_that!(msg1.repeated_int32(), not(eq(msg2.repeated_int32())));
+}
+
+#[test]
+fn test_repeated_int32_get_range() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend(0..5);
+    assert_that!(msg.repeated_int32().get_range(1..3), eq(Some(&[1, 2][..])));
+    assert_that!(msg.repeated_int32().get_range(5..5), eq(Some(&[][..])));
+    assert_that!(msg.repeated_int32().get_range(3..6), eq(None));
+}
+
+#[test]
+fn test_repeated_int32_ref_into_iter() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend([1, 2, 3]);
+    let mut vals = Vec::new();
+    for x in &msg.repeated_int32() {
+        vals.push(x);
+    }
+    assert_that!(vals, eq(vec![1, 2, 3]));
+}
+
+#[test]
+fn test_repeated_int32_push_indexed() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    let indices: Vec<_> = [7, 8, 9].into_iter().map(|v| mutator.push_indexed(v)).collect();
+    assert_that!(indices, eq(vec![0, 1, 2]));
+    assert_that!(mutator.get(2), eq(Some(9)));
+}
+
+#[test]
+fn test_repeated_int32_fold() {
+    let mut msg = TestAllTypes::new();
+    assert_that!(msg.repeated_int32().fold(0, |acc, x| acc + x), eq(0));
+    assert_that!(msg.repeated_int32().reduce(|a, b| a.max(b)), eq(None));
+    msg.repeated_int32_mut().extend([3, 5, 7]);
+    assert_that!(
+        msg.repeated_int32().fold(0, |acc, x| acc + x),
+        eq(msg.repeated_int32().iter().sum::<i32>())
+    );
+    assert_that!(msg.repeated_int32().reduce(|a, b| a.max(b)), eq(Some(7)));
+}
+
+#[test]
+fn test_repeated_int32_windows() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend([1, 2, 3, 4]);
+    let windows: Vec<&[i32]> = msg.repeated_int32().windows(2).collect();
+    assert_that!(windows, eq(vec![&[1, 2][..], &[2, 3][..], &[3, 4][..]]));
+    assert_that!(msg.repeated_int32().windows(5).count(), eq(0));
+}
+
+#[test]
+fn test_repeated_int32_to_array() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend([1, 2, 3]);
+    assert_that!(msg.repeated_int32().to_array::<3>(), eq(Some([1, 2, 3])));
+    assert_that!(msg.repeated_int32().to_array::<2>(), eq(None));
+    let Some([a, b, c]) = msg.repeated_int32().to_array() else { panic!("expected 3 elements") };
+    assert_that!((a, b, c), eq((1, 2, 3)));
+}
+
+#[test]
+fn test_repeated_int32_count_nonzero() {
+    let mut msg = TestAllTypes::new();
+    assert_that!(msg.repeated_int32().count_nonzero(), eq(0));
+    msg.repeated_int32_mut().extend([0, 1, 0, 2, 0]);
+    assert_that!(msg.repeated_int32().count_nonzero(), eq(2));
+}
+
+#[test]
+fn test_fields() {
+    let msg = TestAllTypes::new();
+    let field = |name| msg.fields().find(|f| f.name == name).unwrap();
+
+    let optional_int32 = field("optional_int32");
+    assert_that!(optional_int32.number, eq(1));
+    assert_that!(optional_int32.kind, eq(FieldKind::Scalar));
+    assert_that!(optional_int32.repeated, eq(false));
+    assert_that!(optional_int32.in_oneof, eq(false));
+
+    assert_that!(field("optional_string").kind, eq(FieldKind::String));
+    assert_that!(field("optional_nested_message").kind, eq(FieldKind::Message));
+    assert_that!(field("repeated_int32").repeated, eq(true));
+    assert_that!(field("oneof_uint32").in_oneof, eq(true));
+    assert_that!(msg.fields().next().map(|f| f.number), eq(Some(1)));
+}
+
+#[test]
+fn test_repeated_int32_partition_in_place() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([1, 2, 3, 4, 5]);
+    let pivot = mutator.partition_in_place(|x| x % 2 == 1);
+    assert_that!(pivot, eq(3));
+    assert_that!(mutator.as_slice(), eq([1, 3, 5, 2, 4]));
+
+    mutator.clear();
+    mutator.extend(0..100);
+    let pivot = mutator.partition_in_place(|x| x % 3 == 0);
+    assert_that!(pivot, eq(34));
+    let (matched, rest) = mutator.as_slice().split_at(pivot);
+    assert_that!(matched, eq((0..100).step_by(3).collect::<Vec<_>>()));
+    assert_that!(rest, eq((0..100).filter(|x| x % 3 != 0).collect::<Vec<_>>()));
+}
+
+#[test]
+fn test_repeated_int32_peeking_iter() {
+    let mut msg = TestAllTypes::new();
+    assert_that!(msg.repeated_int32().peeking_iter().next(), eq(None));
+    msg.repeated_int32_mut().extend([1, 2, 3]);
+    assert_that!(
+        msg.repeated_int32().peeking_iter().collect::<Vec<_>>(),
+        eq(vec![(1, Some(2)), (2, Some(3)), (3, None)])
+    );
+}
+
+#[test]
+fn test_repeated_int32_clear() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().clear();
+    assert_that!(msg.repeated_int32().len(), eq(0));
+    msg.repeated_int32_mut().extend([1, 2, 3]);
+    msg.repeated_int32_mut().clear();
+    assert_that!(msg.repeated_int32().len(), eq(0));
+    assert_that!(msg.repeated_int32().get(0), eq(None));
+}
+
+#[test]
+fn test_repeated_int32_truncate() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([1, 2, 3]);
+    mutator.truncate(3);
+    assert_that!(mutator.as_slice(), eq([1, 2, 3]));
+    mutator.truncate(10);
+    assert_that!(mutator.as_slice(), eq([1, 2, 3]));
+    mutator.truncate(1);
+    assert_that!(mutator.as_slice(), eq([1]));
+    mutator.truncate(0);
+    assert_that!(msg.repeated_int32().len(), eq(0));
+}
+
+#[test]
+fn test_repeated_int32_extend_from_slice() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend_from_slice(&[1, 2]);
+    assert_that!(msg.repeated_int32().as_slice(), eq([1, 2]));
+    msg.repeated_int32_mut().extend_from_slice(&[3]);
+    assert_that!(msg.repeated_int32().as_slice(), eq([1, 2, 3]));
+}
+
+#[test]
+fn test_repeated_int32_iter_as() {
+    let mut msg = TestAllTypes::new();
+    msg.repeated_int32_mut().extend([1, 2, 3]);
+    assert_that!(msg.repeated_int32().iter_as::<i64>().sum::<i64>(), eq(6i64));
+    msg.repeated_int32_mut().extend([i32::MAX, i32::MAX]);
+    assert_that!(msg.repeated_int32().iter_as::<i64>().sum::<i64>(), eq(6 + 2 * i32::MAX as i64));
+}
+
+#[test]
+fn test_repeated_int32_reserve() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.reserve(10_000);
+    assert_that!(mutator.capacity(), ge(10_000));
+    mutator.extend(0..10_000);
+    assert_that!(mutator.len(), eq(10_000));
+    assert_that!(mutator.as_slice().iter().copied().eq(0..10_000), eq(true));
+
+    let mut owned = protobuf::OwnedRepeated::<i32>::with_capacity(10_000);
+    assert_that!(owned.as_view().capacity(), ge(10_000));
+    owned.as_mut().extend(0..10_000);
+    assert_that!(owned.as_view(), eq(msg.repeated_int32()));
+}
+
+#[test]
+fn test_repeated_int32_swap_remove() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([1, 2, 3, 4]);
+    assert_that!(mutator.swap_remove(0), eq(Some(1)));
+    assert_that!(mutator.as_slice(), eq([4, 2, 3]));
+    assert_that!(mutator.swap_remove(3), eq(None));
+    assert_that!(msg.repeated_int32().len(), eq(3));
+}
+
+#[test]
+fn test_repeated_int32_remove() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([1, 2, 3, 4, 5]);
+    assert_that!(mutator.remove(2), eq(Some(3)));
+    assert_that!(mutator.as_slice(), eq([1, 2, 4, 5]));
+    assert_that!(mutator.remove(4), eq(None));
+    assert_that!(msg.repeated_int32().len(), eq(4));
+}
+
+#[test]
+fn test_repeated_int32_insert() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([2, 4]);
+    mutator.insert(0, 1);
+    mutator.insert(2, 3);
+    mutator.insert(4, 5);
+    assert_that!(mutator.as_slice(), eq([1, 2, 3, 4, 5]));
+    assert_that!(msg.repeated_int32().len(), eq(5));
+}
+
+#[test]
+fn test_repeated_int32_splice() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([1, 2, 3, 4]);
+    mutator.splice(1..3, &[9, 9, 9]);
+    assert_that!(mutator.as_slice(), eq([1, 9, 9, 9, 4]));
+    assert_that!(msg.repeated_int32().len(), eq(5));
+}
+
+#[test]
+fn test_repeated_int32_retain() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([1, 2, 3, 4, 5, 6]);
+    mutator.retain(|x| x % 2 != 0);
+    assert_that!(mutator.as_slice(), eq([1, 3, 5]));
+    assert_that!(msg.repeated_int32().len(), eq(3));
+}
+
+#[test]
+fn test_repeated_int32_sort() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([3, 1, 2]);
+    mutator.sort();
+    assert_that!(mutator.as_slice(), eq([1, 2, 3]));
+    mutator.sort_by(|a, b| b.cmp(a));
+    assert_that!(mutator.as_slice(), eq([3, 2, 1]));
+}
+
+#[test]
+fn test_repeated_int32_reverse() {
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.extend([1, 2, 3]);
+    mutator.reverse();
+    assert_that!(mutator.as_slice(), eq([3, 2, 1]));
+    assert_that!(msg.repeated_int32().len(), eq(3));
+}
+
+#[test]
+fn test_repeated_int32_extend() {
+    fn append_squares<E: Extend<i32>>(dst: &mut E, n: i32) {
+        dst.extend((1..=n).map(|i| i * i));
+    }
+
+    let mut msg = TestAllTypes::new();
+    let mut mutator = msg.repeated_int32_mut();
+    mutator.push(0);
+    append_squares(&mut mutator, 3);
+    assert_that!(mutator.iter().collect::<Vec<_>>(), eq(vec![0, 1, 4, 9]));
+    assert_that!(msg.repeated_int32().len(), eq(4));
+}
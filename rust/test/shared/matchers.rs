@@ -56,3 +56,15 @@ impl<'a, T: std::fmt::Debug + ProxiedWithPresence + ?Sized> Matcher for SetMatch
         }
     }
 }
+
+/// ===============================
+///               EQ_ELEMENTS
+/// ===============================
+/// Compares two sequences element-wise, e.g. a `RepeatedView` against the
+/// expected values or against a field read through the other kernel.
+pub fn eq_elements<T: PartialEq>(
+    a: impl IntoIterator<Item = T>,
+    b: impl IntoIterator<Item = T>,
+) -> bool {
+    a.into_iter().eq(b)
+}
@@ -6,6 +6,7 @@
 // https://developers.google.com/open-source/licenses/bsd
 
 use googletest::prelude::*;
+use protobuf::{CachedMessage, WireType};
 use unittest_proto::proto2_unittest::TestAllTypes;
 
 #[test]
@@ -37,3 +38,96 @@ fn deserialize_error() {
     let data = b"not a serialized proto";
     assert!(msg.deserialize(&*data).is_err());
 }
+
+#[test]
+fn parse_delimited_stream() {
+    let mut data = Vec::new();
+    for i in 1..=3 {
+        let mut msg = TestAllTypes::new();
+        msg.optional_int32_set(Some(i));
+        let serialized = msg.serialize();
+        data.push(serialized.len() as u8);
+        data.extend_from_slice(&serialized);
+    }
+
+    let msgs: Vec<_> = TestAllTypes::parse_delimited_stream(&data[..])
+        .map(|msg| msg.unwrap().optional_int32())
+        .collect();
+    assert_that!(msgs, eq(vec![1, 2, 3]));
+
+    // A message cut off mid-stream is an error.
+    let mut stream = TestAllTypes::parse_delimited_stream(&data[..data.len() - 1]);
+    assert!(stream.next().unwrap().is_ok());
+    assert!(stream.next().unwrap().is_ok());
+    assert!(stream.next().unwrap().is_err());
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn message_into_vec() {
+    let mut msg = TestAllTypes::new();
+    msg.optional_int32_set(Some(7));
+    msg.optional_bytes_mut().set(b"into vec");
+
+    let bytes: Vec<u8> = (&msg).into();
+    let mut msg2 = TestAllTypes::new();
+    assert!(msg2.deserialize(&bytes).is_ok());
+    assert_that!(msg2.optional_int32(), eq(7));
+    assert_that!(msg2.optional_bytes(), eq(b"into vec"));
+}
+
+#[test]
+fn unknown_field_round_trips() {
+    let mut msg = TestAllTypes::new();
+    msg.optional_int32_set(Some(1));
+    msg.add_unknown_field(9999, WireType::LengthDelimited, b"future");
+    // Field 9999, length-delimited, 6 bytes.
+    let unknown = b"\xfa\xf0\x04\x06future";
+    let serialized = msg.serialize();
+    assert!(serialized.ends_with(unknown));
+
+    let mut reparsed = TestAllTypes::new();
+    assert!(reparsed.deserialize(&serialized).is_ok());
+    assert_that!(reparsed.optional_int32(), eq(1));
+    assert_that!(*reparsed.serialize(), eq(*serialized));
+}
+
+#[test]
+fn cached_message_reserializes_after_mutation() {
+    let mut msg = TestAllTypes::new();
+    msg.optional_int32_set(Some(1));
+    let mut cached = CachedMessage::new(msg);
+
+    let first = cached.serialize().to_vec();
+    assert_that!(cached.is_cached(), eq(true));
+    assert_that!(cached.serialize(), eq(&first[..]));
+
+    cached.get_mut().optional_int32_set(Some(2));
+    assert_that!(cached.is_cached(), eq(false));
+    let second = cached.serialize().to_vec();
+    assert_that!(second, not(eq(first)));
+
+    let mut parsed = TestAllTypes::new();
+    parsed.deserialize(&second).unwrap();
+    assert_that!(parsed.optional_int32(), eq(2));
+}
+
+#[test]
+fn canonical_bytes_ignore_set_order() {
+    let mut msg1 = TestAllTypes::new();
+    msg1.optional_int32_set(Some(1));
+    msg1.optional_bool_set(Some(true));
+    msg1.optional_bytes_mut().set(b"canonical");
+
+    let mut msg2 = TestAllTypes::new();
+    msg2.optional_bytes_mut().set(b"canonical");
+    msg2.optional_bool_set(Some(true));
+    msg2.optional_int32_set(Some(1));
+
+    assert_that!(*msg1.canonical_bytes(), eq(*msg2.canonical_bytes()));
+
+    let mut parsed = TestAllTypes::new();
+    parsed.deserialize(&msg1.canonical_bytes()).unwrap();
+    assert_that!(parsed.optional_int32(), eq(1));
+    assert_that!(parsed.optional_bytes(), eq(b"canonical"));
+}
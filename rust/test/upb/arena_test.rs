@@ -0,0 +1,24 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+use googletest::prelude::*;
+use protobuf::__runtime::Arena;
+use unittest_proto::proto2_unittest::TestAllTypes;
+
+#[test]
+fn adopted_message_outlives_its_own_arena() {
+    let target = Arena::new();
+    let view = {
+        let mut msg = TestAllTypes::new();
+        msg.optional_int32_set(Some(42));
+        msg.optional_int64_set(Some(7));
+        // `msg`, and its handle on its own arena, are dropped here.
+        msg.adopt_into_arena(&target)
+    };
+    assert_that!(view.optional_int32(), eq(42));
+    assert_that!(view.optional_int64(), eq(7));
+}
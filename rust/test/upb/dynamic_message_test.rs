@@ -0,0 +1,87 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Tests that dynamic messages agree with the generated code for the same
+//! message type.
+
+use googletest::prelude::*;
+use protobuf::{transcode_wire_to_json, DefPool, DynamicMessage, DynamicValue};
+use unittest_proto::proto2_unittest::TestAllTypes;
+
+const UNITTEST_DESCRIPTOR_SET: &[u8] = include_bytes!(env!("UNITTEST_DESCRIPTOR_SET"));
+
+fn unittest_pool() -> DefPool {
+    let mut pool = DefPool::new();
+    pool.add_serialized_file_descriptor_set(UNITTEST_DESCRIPTOR_SET).unwrap();
+    pool
+}
+
+#[test]
+fn parse_generated_message() {
+    let mut msg = TestAllTypes::new();
+    msg.optional_int32_set(Some(42));
+    msg.optional_string_mut().set("hello");
+    msg.optional_bytes_mut().set(b"world");
+
+    let pool = unittest_pool();
+    let desc = pool.find_message("protobuf_unittest.TestAllTypes").unwrap();
+    let dynamic = DynamicMessage::parse(desc, &msg.serialize()).unwrap();
+    assert_that!(dynamic.get_field_by_name("optional_int32"), eq(Some(DynamicValue::I32(42))));
+    assert_that!(
+        dynamic.get_field_by_name("optional_string"),
+        eq(Some(DynamicValue::String("hello".into())))
+    );
+    assert_that!(
+        dynamic.get_field_by_name("optional_bytes"),
+        eq(Some(DynamicValue::Bytes(b"world")))
+    );
+    assert_that!(dynamic.get_field_by_name("optional_int64"), eq(Some(DynamicValue::I64(0))));
+}
+
+#[test]
+fn field_default_matches_generated_accessors() {
+    let pool = unittest_pool();
+    let desc = pool.find_message("protobuf_unittest.TestAllTypes").unwrap();
+    let msg = TestAllTypes::new();
+    assert_that!(
+        desc.field_default("default_int32"),
+        eq(Some(DynamicValue::I32(msg.default_int32())))
+    );
+    assert_that!(
+        desc.field_default("default_int64"),
+        eq(Some(DynamicValue::I64(msg.default_int64())))
+    );
+    assert_that!(
+        desc.field_default("default_bool"),
+        eq(Some(DynamicValue::Bool(msg.default_bool())))
+    );
+    assert_that!(
+        desc.field_default("default_string"),
+        eq(Some(DynamicValue::String(msg.default_string())))
+    );
+    assert_that!(
+        desc.field_default("default_bytes"),
+        eq(Some(DynamicValue::Bytes(msg.default_bytes())))
+    );
+    assert_that!(msg.default_int32(), eq(41));
+    assert_that!(msg.default_string(), eq("hello"));
+}
+
+#[test]
+fn transcode_generated_message_to_json() {
+    let mut msg = TestAllTypes::new();
+    msg.optional_int32_set(Some(42));
+    msg.optional_int64_set(Some(7));
+    msg.optional_string_mut().set("hello");
+
+    let pool = unittest_pool();
+    let desc = pool.find_message("protobuf_unittest.TestAllTypes").unwrap();
+    assert_that!(
+        transcode_wire_to_json(&msg.serialize(), desc).unwrap(),
+        eq(r#"{"optionalInt32":42,"optionalInt64":"7","optionalString":"hello"}"#)
+    );
+}
@@ -7,14 +7,21 @@
 
 //! UPB FFI wrapper code for use by Rust Protobuf.
 
-use crate::__internal::{Private, PtrAndLen, RawArena, RawMap, RawMessage, RawRepeatedField};
+use crate::__internal::{
+    Private, PtrAndLen, RawArena, RawDefPool, RawFieldDef, RawMap, RawMessage, RawMessageDef,
+    RawRepeatedField,
+};
+use crate::{AllocError, DynamicValue, FieldError, LengthMismatch, ParseError, ProtoStr};
 use std::alloc;
 use std::alloc::Layout;
-use std::cell::UnsafeCell;
+use std::cell::{RefCell, UnsafeCell};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ffi::{c_char, c_void, CStr};
 use std::fmt;
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 use std::ptr::{self, NonNull};
 use std::slice;
 use std::sync::Once;
@@ -22,6 +29,10 @@ use std::sync::Once;
 /// See `upb/port/def.inc`.
 const UPB_MALLOC_ALIGN: usize = 8;
 
+/// `kUpb_EncodeOption_Deterministic` from `upb/wire/encode.h`, which sorts map
+/// entries by key.
+pub const ENCODE_DETERMINISTIC: i32 = 1;
+
 /// A wrapper over a `upb_Arena`.
 ///
 /// This is not a safe wrapper per se, because the allocation functions still
@@ -44,6 +55,7 @@ extern "C" {
     fn upb_Arena_Free(arena: RawArena);
     fn upb_Arena_Malloc(arena: RawArena, size: usize) -> *mut u8;
     fn upb_Arena_Realloc(arena: RawArena, ptr: *mut u8, old: usize, new: usize) -> *mut u8;
+    fn upb_Arena_Fuse(a: RawArena, b: RawArena) -> bool;
 }
 
 impl Arena {
@@ -65,6 +77,25 @@ impl Arena {
         }
     }
 
+    /// Runs `f` with a fresh arena that is freed as soon as `f` returns.
+    ///
+    /// Anything allocated on the arena borrows from it, so the borrow checker
+    /// rejects any attempt to return such data from `f`; copy out what should
+    /// outlive the scope.
+    pub fn scope<R>(f: impl FnOnce(&Arena) -> R) -> R {
+        f(&Arena::new())
+    }
+
+    /// Joins the lifetimes of `self` and `other`: memory allocated on either
+    /// one is freed only once both have been dropped.
+    ///
+    /// Returns `false` if either arena was created over a caller-provided
+    /// initial block, whose lifetime can't be extended.
+    pub fn fuse(&self, other: &Arena) -> bool {
+        // SAFETY: `self.raw` and `other.raw` are valid UPB arenas.
+        unsafe { upb_Arena_Fuse(self.raw, other.raw) }
+    }
+
     /// Returns the raw, UPB-managed pointer to the arena.
     #[inline]
     pub fn raw(&self) -> RawArena {
@@ -94,6 +125,29 @@ impl Arena {
         unsafe { slice::from_raw_parts_mut(ptr.cast(), layout.size()) }
     }
 
+    /// Allocates some memory on the arena, with any alignment.
+    ///
+    /// Alignments above `UPB_MALLOC_ALIGN` are satisfied by over-allocating
+    /// and returning an aligned sub-slice, so the padding is wasted until the
+    /// arena is destroyed.
+    pub fn alloc_aligned(&self, layout: Layout) -> &mut [MaybeUninit<u8>] {
+        if layout.align() <= UPB_MALLOC_ALIGN {
+            // SAFETY: the alignment was just checked.
+            return unsafe { self.alloc(layout) };
+        }
+        let padded = layout
+            .size()
+            .checked_add(layout.align() - UPB_MALLOC_ALIGN)
+            .and_then(|size| Layout::from_size_align(size, UPB_MALLOC_ALIGN).ok())
+            .expect("allocation too large");
+        // SAFETY: `padded` has an alignment of `UPB_MALLOC_ALIGN`.
+        let block = unsafe { self.alloc(padded) };
+        // `block` is `UPB_MALLOC_ALIGN`-aligned, so the offset is at most the
+        // padding that was added.
+        let offset = block.as_ptr().align_offset(layout.align());
+        &mut block[offset..offset + layout.size()]
+    }
+
     /// Resizes some memory on the arena.
     ///
     /// # Safety
@@ -135,6 +189,44 @@ impl Drop for Arena {
     }
 }
 
+/// Owns a set of arenas and frees all of them together when dropped, e.g. at
+/// the end of a request.
+#[derive(Debug, Default)]
+pub struct ArenaGroup {
+    // Each arena is boxed so that references handed out by `new_arena` stay
+    // valid when the `Vec` reallocates.
+    arenas: RefCell<Vec<Box<Arena>>>,
+}
+
+impl ArenaGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates a fresh arena that lives until the group is dropped.
+    pub fn new_arena(&self) -> &Arena {
+        self.adopt(Arena::new())
+    }
+
+    /// Returns the number of arenas in the group.
+    pub fn len(&self) -> usize {
+        self.arenas.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn adopt(&self, arena: Arena) -> &Arena {
+        let mut arenas = self.arenas.borrow_mut();
+        arenas.push(Box::new(arena));
+        let arena: *const Arena = &**arenas.last().unwrap();
+        // SAFETY: arenas are only ever pushed, never removed or moved out of
+        // their boxes, so the arena lives as long as `self`.
+        unsafe { &*arena }
+    }
+}
+
 static mut INTERNAL_PTR: Option<RawMessage> = None;
 static INIT: Once = Once::new();
 
@@ -193,6 +285,11 @@ impl SerializedData {
     pub fn as_ptr(&self) -> *const [u8] {
         ptr::slice_from_raw_parts(self.data.as_ptr(), self.len)
     }
+
+    /// Returns the bytes in `range`, or `None` if it is out of bounds.
+    pub fn slice(&self, range: std::ops::Range<usize>) -> Option<&[u8]> {
+        self.get(range)
+    }
 }
 
 impl Deref for SerializedData {
@@ -223,6 +320,18 @@ pub struct MessageInner {
     pub arena: Arena,
 }
 
+impl MessageInner {
+    /// Appends `data`, which must be serialized fields, to the message's
+    /// unknown fields. upb copies `data` into the message's arena.
+    pub fn add_unknown(&mut self, data: &[u8]) {
+        // SAFETY: generated messages always hold a valid message allocated on
+        // their own arena.
+        unsafe {
+            upb_Message_AddUnknown(self.msg, data.as_ptr().cast(), data.len(), self.arena.raw())
+        }
+    }
+}
+
 /// Mutators that point to their original message use this to do so.
 ///
 /// Since UPB expects runtimes to manage their own arenas, this needs to have
@@ -284,6 +393,30 @@ pub fn copy_bytes_in_arena_if_needed_by_runtime<'a>(
     }
 }
 
+/// Copies `head` followed by `tail` into a single arena allocation, so a
+/// concatenated value can be set without building it on the heap first.
+pub fn concat_bytes_in_arena_if_needed_by_runtime<'a>(
+    msg_ref: MutatorMessageRef<'a>,
+    head: &[u8],
+    tail: &[u8],
+) -> &'a [u8] {
+    let len = head.len() + tail.len();
+    // SAFETY: the alignment of `[u8]` is less than `UPB_MALLOC_ALIGN`.
+    let new_alloc = unsafe { msg_ref.arena.alloc(Layout::array::<u8>(len).unwrap()) };
+    debug_assert_eq!(new_alloc.len(), len);
+
+    let start: *mut u8 = new_alloc.as_mut_ptr().cast();
+    // SAFETY:
+    // - `new_alloc` is writeable for `len` bytes, and does not overlap either input
+    //   since it was just allocated.
+    // - After both copies, `new_alloc` is initialized for `len` bytes.
+    unsafe {
+        head.as_ptr().copy_to_nonoverlapping(start, head.len());
+        tail.as_ptr().copy_to_nonoverlapping(start.add(head.len()), tail.len());
+        &*(new_alloc as *mut _ as *mut [u8])
+    }
+}
+
 /// RepeatedFieldInner contains a `upb_Array*` as well as a reference to an
 /// `Arena`, most likely that of the containing `Message`. upb requires an Arena
 /// to perform mutations on a repeated field.
@@ -316,11 +449,60 @@ impl<'msg, T: ?Sized> RepeatedField<'msg, T> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+    /// Returns the number of elements the array can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        unsafe { upb_Array_Capacity(self.inner.raw) }
+    }
+    /// Reserves capacity for at least `additional` more elements.
+    pub fn reserve(&mut self, additional: usize) {
+        let size = self.len().checked_add(additional).expect("capacity overflow");
+        let ok = unsafe { upb_Array_Reserve(self.inner.raw, size, self.inner.arena.raw()) };
+        assert!(ok, "failed to reserve capacity for {size} elements");
+    }
+    /// Reduces the capacity to `len()`. Arena memory is only given back if
+    /// the array's buffer was the arena's most recent allocation.
+    pub fn shrink_to_fit(&mut self) {
+        unsafe { upb_Array_ShrinkToFit(self.inner.raw, self.inner.arena.raw()) }
+    }
+    /// Removes all elements, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        // Empty arrays are skipped so that the shared `empty_array()` is never
+        // written to.
+        if self.is_empty() {
+            return;
+        }
+        // Shrinking never allocates, so this cannot fail.
+        unsafe { upb_Array_Resize(self.inner.raw, 0, self.inner.arena.raw()) };
+    }
+    /// Shortens the array to its first `len` elements. Does nothing if `len`
+    /// is not less than `self.len()`.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len() {
+            return;
+        }
+        // Shrinking never allocates, so this cannot fail.
+        unsafe { upb_Array_Resize(self.inner.raw, len, self.inner.arena.raw()) };
+    }
     pub fn from_inner(_private: Private, inner: RepeatedFieldInner<'msg>) -> Self {
         Self { inner, _phantom: PhantomData }
     }
 }
 
+/// A `upb_Array` that is not owned by any message. It allocates from, and
+/// keeps alive, its own `Arena`.
+#[derive(Debug)]
+pub struct OwnedRepeatedField<T: ?Sized> {
+    arena: Arena,
+    raw: RawRepeatedField,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: ?Sized> OwnedRepeatedField<T> {
+    pub fn as_inner(&self) -> RepeatedFieldInner<'_> {
+        RepeatedFieldInner { raw: self.raw, arena: &self.arena }
+    }
+}
+
 // Transcribed from google3/third_party/upb/upb/message/value.h
 #[repr(C)]
 #[derive(Clone, Copy)]
@@ -362,14 +544,28 @@ extern "C" {
     fn upb_Array_Set(arr: RawRepeatedField, i: usize, val: upb_MessageValue);
     fn upb_Array_Get(arr: RawRepeatedField, i: usize) -> upb_MessageValue;
     fn upb_Array_Append(arr: RawRepeatedField, val: upb_MessageValue, arena: RawArena);
-    fn upb_Array_Resize(arr: RawRepeatedField, size: usize, arena: RawArena);
+    fn upb_Array_Resize(arr: RawRepeatedField, size: usize, arena: RawArena) -> bool;
+    fn upb_Array_DataPtr(arr: RawRepeatedField) -> *const std::ffi::c_void;
+    fn upb_Array_MutableDataPtr(arr: RawRepeatedField) -> *mut std::ffi::c_void;
+    fn upb_Array_Capacity(arr: RawRepeatedField) -> usize;
+    fn upb_Array_Reserve(arr: RawRepeatedField, size: usize, arena: RawArena) -> bool;
+    fn upb_Array_ShrinkToFit(arr: RawRepeatedField, arena: RawArena);
+    fn upb_Array_ElementSize(arr: RawRepeatedField) -> usize;
 }
 
 macro_rules! impl_repeated_primitives {
     ($(($rs_type:ty, $union_field:ident, $upb_tag:expr)),*) => {
         $(
+            impl OwnedRepeatedField<$rs_type> {
+                #[allow(clippy::new_without_default)]
+                pub fn new() -> Self {
+                    let arena = Arena::new();
+                    let raw = RepeatedField::<$rs_type>::new(&arena).inner.raw;
+                    Self { arena, raw, _phantom: PhantomData }
+                }
+            }
+
             impl<'msg> RepeatedField<'msg, $rs_type> {
-                #[allow(dead_code)]
                 fn new(arena: &'msg Arena) -> Self {
                     Self {
                         inner: RepeatedFieldInner {
@@ -403,18 +599,158 @@ macro_rules! impl_repeated_primitives {
                         upb_MessageValue { $union_field: val },
                     ) }
                 }
+                /// Removes and returns the element at `index`, replacing it with the last
+                /// element. Returns `None` and leaves the array untouched if `index` is
+                /// out of bounds.
+                pub fn swap_remove(&mut self, index: usize) -> Option<$rs_type> {
+                    let removed = self.get(index)?;
+                    let last = self.len() - 1;
+                    self.set(index, self.get(last).unwrap());
+                    self.truncate(last);
+                    Some(removed)
+                }
+                /// Sorts the elements in place with `compare`. The sort is unstable: equal
+                /// elements may be reordered, and nothing is allocated.
+                pub fn sort_by<F>(&mut self, compare: F)
+                where
+                    F: FnMut(&$rs_type, &$rs_type) -> Ordering,
+                {
+                    self.as_mut_slice().sort_unstable_by(compare)
+                }
+                /// Keeps only the elements for which `f` returns `true`, in their original
+                /// order, compacting them in place.
+                pub fn retain<F: FnMut(&$rs_type) -> bool>(&mut self, mut f: F) {
+                    let data = self.as_mut_slice();
+                    let mut kept = 0;
+                    for i in 0..data.len() {
+                        if f(&data[i]) {
+                            data.swap(kept, i);
+                            kept += 1;
+                        }
+                    }
+                    self.truncate(kept);
+                }
+                /// Replaces the elements in `range` with `replace_with`, shifting the
+                /// elements after it as needed, like `Vec::splice`.
+                ///
+                /// # Panics
+                /// Panics if `range` is decreasing or out of bounds.
+                pub fn splice(&mut self, range: Range<usize>, replace_with: &[$rs_type]) {
+                    let len = self.len();
+                    assert!(
+                        range.start <= range.end && range.end <= len,
+                        "range {range:?} out of bounds for length {len}"
+                    );
+                    let new_len = len - range.len() + replace_with.len();
+                    if new_len > len {
+                        let resized = unsafe {
+                            upb_Array_Resize(self.inner.raw, new_len, self.inner.arena.raw())
+                        };
+                        assert!(resized, "upb_Array_Resize failed");
+                    }
+                    let data = self.as_mut_slice();
+                    let replaced_end = range.start + replace_with.len();
+                    data.copy_within(range.end..len, replaced_end);
+                    data[range.start..replaced_end].copy_from_slice(replace_with);
+                    self.truncate(new_len);
+                }
+                /// Inserts `val` at `index`, shifting all elements after it up by one.
+                ///
+                /// # Panics
+                /// Panics if `index > len()`.
+                pub fn insert(&mut self, index: usize, val: $rs_type) {
+                    let len = self.len();
+                    assert!(index <= len, "insertion index {index} out of bounds for length {len}");
+                    self.push(val);
+                    self.as_mut_slice()[index..].rotate_right(1);
+                }
+                /// Removes and returns the element at `index`, shifting all elements after
+                /// it down by one. Returns `None` and leaves the array untouched if `index`
+                /// is out of bounds.
+                pub fn remove(&mut self, index: usize) -> Option<$rs_type> {
+                    let removed = self.get(index)?;
+                    let len = self.len();
+                    self.as_mut_slice().copy_within(index + 1.., index);
+                    self.truncate(len - 1);
+                    Some(removed)
+                }
+                /// Checks that upb stores elements of this array with the size of
+                /// `$rs_type`, which the slice accessors rely on.
+                fn debug_assert_element_size(&self) {
+                    debug_assert_eq!(
+                        unsafe { upb_Array_ElementSize(self.inner.raw) },
+                        std::mem::size_of::<$rs_type>(),
+                    );
+                }
+                pub fn as_slice(&self) -> &[$rs_type] {
+                    let len = self.len();
+                    if len == 0 {
+                        return &[];
+                    }
+                    self.debug_assert_element_size();
+                    // SAFETY:
+                    // - `upb_Array_DataPtr` points to `len` contiguous, initialized elements
+                    //   of the array's element type, which is `$rs_type`.
+                    // - The array cannot be mutated while the returned slice borrows `self`.
+                    unsafe {
+                        slice::from_raw_parts(
+                            upb_Array_DataPtr(self.inner.raw).cast::<$rs_type>(),
+                            len,
+                        )
+                    }
+                }
+                pub fn as_mut_slice(&mut self) -> &mut [$rs_type] {
+                    let len = self.len();
+                    if len == 0 {
+                        return &mut [];
+                    }
+                    self.debug_assert_element_size();
+                    // SAFETY:
+                    // - `upb_Array_MutableDataPtr` points to `len` contiguous, initialized
+                    //   elements of the array's element type, which is `$rs_type`.
+                    // - The returned slice borrows `self` mutably, so the array cannot be
+                    //   resized or otherwise accessed through `self` while it is alive.
+                    unsafe {
+                        slice::from_raw_parts_mut(
+                            upb_Array_MutableDataPtr(self.inner.raw).cast::<$rs_type>(),
+                            len,
+                        )
+                    }
+                }
+                /// Overwrites every element with the corresponding element of `src`.
+                ///
+                /// # Panics
+                /// Panics if `src.len() != self.len()`.
+                pub fn clone_from_slice(&mut self, src: &[$rs_type]) {
+                    self.as_mut_slice().clone_from_slice(src)
+                }
+                /// Appends all of `src` with a single resize and bulk copy.
+                pub fn extend_from_slice(&mut self, src: &[$rs_type]) {
+                    if src.is_empty() {
+                        return;
+                    }
+                    let len = self.len();
+                    let new_len = len.checked_add(src.len()).expect("capacity overflow");
+                    let resized = unsafe {
+                        upb_Array_Resize(self.inner.raw, new_len, self.inner.arena.raw())
+                    };
+                    assert!(resized, "upb_Array_Resize failed");
+                    self.as_mut_slice()[len..].copy_from_slice(src);
+                }
                 pub fn copy_from(&mut self, src: &RepeatedField<'_, $rs_type>) {
-                    // TODO: Optimize this copy_from implementation using memcopy.
                     // NOTE: `src` cannot be `self` because this would violate borrowing rules.
-                    unsafe { upb_Array_Resize(self.inner.raw, 0, self.inner.arena.raw()) };
                     // `upb_Array_DeepClone` is not used here because it returns
                     // a new `upb_Array*`. The contained `RawRepeatedField` must
                     // then be set to this new pointer, but other copies of this
                     // pointer may exist because of re-borrowed `RepeatedMut`s.
                     // Alternatively, a `clone_into` method could be exposed by upb.
-                    for i in 0..src.len() {
-                        self.push(src.get(i).unwrap());
-                    }
+                    let resized = unsafe {
+                        upb_Array_Resize(self.inner.raw, src.len(), self.inner.arena.raw())
+                    };
+                    assert!(resized, "upb_Array_Resize failed");
+                    // Both slices are sized in elements and checked against upb's
+                    // element size, so this copies exactly `src.len()` elements.
+                    self.as_mut_slice().copy_from_slice(src.as_slice());
                 }
             }
         )*
@@ -431,6 +767,22 @@ impl_repeated_primitives!(
     (u64, uint64_val, UpbCType::UInt64)
 );
 
+macro_rules! impl_repeated_ord {
+    ($($t:ty),*) => {
+        $(
+            impl<'msg> RepeatedField<'msg, $t> {
+                /// Sorts the elements in place, without allocating.
+                pub fn sort(&mut self) {
+                    self.as_mut_slice().sort_unstable()
+                }
+            }
+        )*
+    }
+}
+
+// Floats are not `Ord`; use `sort_by` with `total_cmp` for them.
+impl_repeated_ord!(bool, i32, u32, i64, u64);
+
 /// Returns a static thread-local empty RepeatedFieldInner for use in a
 /// RepeatedView.
 ///
@@ -514,6 +866,12 @@ impl<'msg, K: ?Sized, V: ?Sized> Map<'msg, K, V> {
     pub fn clear(&mut self) {
         unsafe { upb_Map_Clear(self.inner.raw) }
     }
+
+    /// Rehashes the entries into the smallest table that holds them. The
+    /// memory of the old table is only reclaimed when the arena is dropped.
+    pub fn shrink_to_fit(&mut self) {
+        unsafe { upb_Map_ShrinkToFit(self.inner.raw, self.inner.arena.raw()) }
+    }
 }
 
 /// # Safety
@@ -607,6 +965,11 @@ impl<'msg, K: MapKeyType, V: MapValueType> Map<'msg, K, V> {
         Some(unsafe { V::unpack_message_value(Private, val) })
     }
 
+    /// Returns the value for `key`, or `default` if it is absent.
+    pub fn get_or(&self, key: K, default: V) -> V {
+        self.get(key).unwrap_or(default)
+    }
+
     pub fn insert(&mut self, key: K, value: V) -> bool {
         unsafe {
             upb_Map_Set(
@@ -618,6 +981,25 @@ impl<'msg, K: MapKeyType, V: MapValueType> Map<'msg, K, V> {
         }
     }
 
+    /// Like `insert`, but reports allocation failure instead of hiding it.
+    /// Returns `Ok(true)` if `key` was newly inserted and `Ok(false)` if its
+    /// value was replaced.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<bool, AllocError> {
+        let status = unsafe {
+            upb_Map_Insert(
+                self.inner.raw,
+                K::pack_message_value(Private, key),
+                V::pack_message_value(Private, value),
+                self.inner.arena.raw(),
+            )
+        };
+        match status {
+            upb_MapInsertStatus::Inserted => Ok(true),
+            upb_MapInsertStatus::Replaced => Ok(false),
+            upb_MapInsertStatus::OutOfMemory => Err(AllocError),
+        }
+    }
+
     pub fn remove(&mut self, key: K) -> Option<V> {
         let mut val = V::pack_message_value(Private, V::zero_value(Private));
         let removed = unsafe {
@@ -628,6 +1010,145 @@ impl<'msg, K: MapKeyType, V: MapValueType> Map<'msg, K, V> {
         }
         Some(unsafe { V::unpack_message_value(Private, val) })
     }
+
+    pub fn contains_key(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns an iterator over the entries, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        let raw = self.inner.raw;
+        let mut iter = UPB_MAP_BEGIN;
+        std::iter::from_fn(move || {
+            let mut key = K::pack_message_value(Private, K::zero_value(Private));
+            let mut val = V::pack_message_value(Private, V::zero_value(Private));
+            // SAFETY: `upb_Map_Next` populates `key` and `val` with the active variants
+            // for `K` and `V` whenever it returns `true`.
+            unsafe {
+                upb_Map_Next(raw, &mut key, &mut val, &mut iter).then(|| {
+                    (K::unpack_message_value(Private, key), V::unpack_message_value(Private, val))
+                })
+            }
+        })
+    }
+
+    /// Inserts `keys[i] => values[i]` for every `i`, in order, so later
+    /// duplicates of a key win. Returns an error without inserting anything if
+    /// the slices have different lengths.
+    pub fn insert_from_slices(&mut self, keys: &[K], values: &[V]) -> Result<(), LengthMismatch>
+    where
+        K: Copy,
+        V: Copy,
+    {
+        if keys.len() != values.len() {
+            return Err(LengthMismatch);
+        }
+        for (&k, &v) in keys.iter().zip(values) {
+            self.insert(k, v);
+        }
+        Ok(())
+    }
+
+    /// Copies the entries into a `BTreeMap`, which iterates in key order.
+    pub fn to_btree_map(&self) -> BTreeMap<K, V>
+    where
+        K: Ord,
+    {
+        self.iter().collect()
+    }
+
+    /// Returns the number of entries for which `f` returns `true`.
+    pub fn count_matching<F: FnMut(K, &V) -> bool>(&self, mut f: F) -> usize {
+        let mut key = K::pack_message_value(Private, K::zero_value(Private));
+        let mut val = V::pack_message_value(Private, V::zero_value(Private));
+        let mut iter = UPB_MAP_BEGIN;
+        let mut count = 0;
+        // SAFETY: `upb_Map_Next` populates `key` and `val` with the active variants
+        // for `K` and `V` whenever it returns `true`.
+        while unsafe { upb_Map_Next(self.inner.raw, &mut key, &mut val, &mut iter) } {
+            let (k, v) = unsafe {
+                (K::unpack_message_value(Private, key), V::unpack_message_value(Private, val))
+            };
+            if f(k, &v) {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Inserts every entry of `other` into `self`, overwriting the values of
+    /// keys present in both.
+    pub fn merge_from(&mut self, other: &Map<'_, K, V>) {
+        let mut key = K::pack_message_value(Private, K::zero_value(Private));
+        let mut val = V::pack_message_value(Private, V::zero_value(Private));
+        let mut iter = UPB_MAP_BEGIN;
+        // SAFETY: `K` and `V` are scalars, so the entries do not borrow from
+        // `other`'s arena and can be copied into `self` as-is.
+        while unsafe { upb_Map_Next(other.inner.raw, &mut key, &mut val, &mut iter) } {
+            unsafe { upb_Map_Set(self.inner.raw, key, val, self.inner.arena.raw()) };
+        }
+    }
+}
+
+impl<'msg, V: MapValueType> Map<'msg, ProtoStr, V> {
+    pub fn new(arena: &'msg Arena) -> Self {
+        unsafe {
+            let raw_map = upb_Map_New(arena.raw(), UpbCType::String, V::upb_ctype(Private));
+            Map {
+                inner: MapInner { raw: raw_map, arena },
+                _phantom_key: PhantomData,
+                _phantom_value: PhantomData,
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<V> {
+        let mut val = V::pack_message_value(Private, V::zero_value(Private));
+        let found = unsafe { upb_Map_Get(self.inner.raw, pack_str_key(key), &mut val) };
+        if !found {
+            return None;
+        }
+        Some(unsafe { V::unpack_message_value(Private, val) })
+    }
+
+    pub fn insert(&mut self, key: &str, value: V) -> bool {
+        // upb copies the key bytes into the map's arena.
+        unsafe {
+            upb_Map_Set(
+                self.inner.raw,
+                pack_str_key(key),
+                V::pack_message_value(Private, value),
+                self.inner.arena.raw(),
+            )
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let mut val = V::pack_message_value(Private, V::zero_value(Private));
+        let removed = unsafe { upb_Map_Delete(self.inner.raw, pack_str_key(key), &mut val) };
+        if !removed {
+            return None;
+        }
+        Some(unsafe { V::unpack_message_value(Private, val) })
+    }
+}
+
+/// Borrows `key` as a string map key without copying it.
+fn pack_str_key(key: &str) -> upb_MessageValue {
+    upb_MessageValue { str_val: PtrAndLen { ptr: key.as_ptr(), len: key.len() } }
+}
+
+/// Iterator cursor value that starts a `upb_Map_Next` iteration.
+const UPB_MAP_BEGIN: usize = usize::MAX;
+
+// Transcribed from google3/third_party/upb/upb/message/map.h
+#[repr(C)]
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum upb_MapInsertStatus {
+    Inserted = 0,
+    Replaced = 1,
+    OutOfMemory = 2,
 }
 
 extern "C" {
@@ -639,6 +1160,12 @@ extern "C" {
         value: upb_MessageValue,
         arena: RawArena,
     ) -> bool;
+    fn upb_Map_Insert(
+        map: RawMap,
+        key: upb_MessageValue,
+        value: upb_MessageValue,
+        arena: RawArena,
+    ) -> upb_MapInsertStatus;
     fn upb_Map_Get(map: RawMap, key: upb_MessageValue, value: *mut upb_MessageValue) -> bool;
     fn upb_Map_Delete(
         map: RawMap,
@@ -646,6 +1173,406 @@ extern "C" {
         removed_value: *mut upb_MessageValue,
     ) -> bool;
     fn upb_Map_Clear(map: RawMap);
+    fn upb_Map_ShrinkToFit(map: RawMap, arena: RawArena);
+    fn upb_Map_Next(
+        map: RawMap,
+        key: *mut upb_MessageValue,
+        value: *mut upb_MessageValue,
+        iter: *mut usize,
+    ) -> bool;
+}
+
+// Transcribed from google3/third_party/upb/upb/base/status.h
+#[repr(C)]
+struct upb_Status {
+    ok: bool,
+    msg: [c_char; 127],
+}
+
+/// An owned `upb_DefPool`, which owns every def added to it.
+#[derive(Debug)]
+pub struct DefPoolInner {
+    raw: RawDefPool,
+}
+
+impl DefPoolInner {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let raw = unsafe { upb_DefPool_New() };
+        Self { raw: raw.expect("upb_DefPool_New failed") }
+    }
+
+    /// Parses a serialized `FileDescriptorProto` and builds its defs. On
+    /// failure, returns the error message reported by upb.
+    pub fn add_serialized_file(&mut self, data: &[u8]) -> Result<(), String> {
+        let mut status = MaybeUninit::<upb_Status>::uninit();
+        // SAFETY: `upb_Status_Clear` initializes `status`, and
+        // `upb_DefPool_AddSerializedFile` only reads `data` for the duration of
+        // the call.
+        unsafe {
+            upb_Status_Clear(status.as_mut_ptr());
+            let file = upb_DefPool_AddSerializedFile(
+                self.raw,
+                data.as_ptr().cast(),
+                data.len(),
+                status.as_mut_ptr(),
+            );
+            if file.is_null() {
+                let msg = CStr::from_ptr(upb_Status_ErrorMessage(status.as_ptr()));
+                return Err(msg.to_string_lossy().into_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Looks up a message def by its fully-qualified name.
+    pub fn find_message(&self, full_name: &str) -> Option<RawMessageDef> {
+        unsafe {
+            upb_DefPool_FindMessageByNameWithSize(
+                self.raw,
+                full_name.as_ptr().cast(),
+                full_name.len(),
+            )
+        }
+    }
+}
+
+impl Drop for DefPoolInner {
+    fn drop(&mut self) {
+        unsafe { upb_DefPool_Free(self.raw) }
+    }
+}
+
+/// # Safety
+/// `def` must be owned by a `DefPoolInner` that outlives `'pool`.
+pub unsafe fn message_def_full_name<'pool>(def: RawMessageDef) -> &'pool str {
+    // SAFETY: upb returns a NUL-terminated name owned by the pool. Full names
+    // are validated to be ASCII identifiers when the file is added.
+    unsafe {
+        CStr::from_ptr(upb_MessageDef_FullName(def))
+            .to_str()
+            .expect("upb message names are always ASCII")
+    }
+}
+
+/// # Safety
+/// `def` must be owned by a live `DefPoolInner`.
+pub unsafe fn message_def_field_count(def: RawMessageDef) -> usize {
+    unsafe { upb_MessageDef_FieldCount(def) as usize }
+}
+
+/// Returns the declared default of the singular field named `name`, or `None`
+/// if there is no such field or it is a message field.
+///
+/// # Safety
+/// `def` must be owned by a `DefPoolInner` that outlives `'pool`.
+pub unsafe fn message_def_field_default<'pool>(
+    def: RawMessageDef,
+    name: &str,
+) -> Option<DynamicValue<'pool>> {
+    // SAFETY: default string values are owned by the pool.
+    unsafe {
+        let field = find_singular_field(def, name)?;
+        to_dynamic_value(field, upb_FieldDef_Default(field))
+    }
+}
+
+/// A message whose type is given by a runtime `upb_MessageDef` rather than by
+/// generated code. It owns the arena its contents are allocated on.
+#[derive(Debug)]
+pub struct DynamicMessageInner {
+    msg: RawMessage,
+    arena: Arena,
+}
+
+impl DynamicMessageInner {
+    /// # Safety
+    /// `def` must be owned by a live `DefPoolInner`.
+    pub unsafe fn new(def: RawMessageDef) -> Self {
+        let arena = Arena::new();
+        let msg = unsafe { upb_Message_New(upb_MessageDef_MiniTable(def), arena.raw()) };
+        Self { msg: msg.expect("upb_Message_New failed"), arena }
+    }
+
+    /// Replaces the contents of this message with `data` parsed as `def`.
+    ///
+    /// # Safety
+    /// `def` must be the def this message was created with, and be owned by a
+    /// live `DefPoolInner`.
+    pub unsafe fn parse(&mut self, def: RawMessageDef, data: &[u8]) -> Result<(), ParseError> {
+        let parsed = unsafe { Self::new(def) };
+        let status = unsafe {
+            upb_Decode(
+                data.as_ptr().cast(),
+                data.len(),
+                parsed.msg,
+                upb_MessageDef_MiniTable(def),
+                ptr::null(),
+                0,
+                parsed.arena.raw(),
+            )
+        };
+        if status != 0 {
+            return Err(ParseError);
+        }
+        // This drops the previous arena and the message allocated on it.
+        *self = parsed;
+        Ok(())
+    }
+
+    /// # Safety
+    /// `def` must be the def this message was created with, and be owned by a
+    /// live `DefPoolInner`.
+    pub unsafe fn serialize(&self, def: RawMessageDef) -> SerializedData {
+        let arena = Arena::new();
+        let mut data = ptr::null_mut();
+        let mut len = 0;
+        let status = unsafe {
+            upb_Encode(self.msg, upb_MessageDef_MiniTable(def), 0, arena.raw(), &mut data, &mut len)
+        };
+        assert_eq!(status, 0, "upb_Encode failed");
+        let data = NonNull::new(data).unwrap_or(NonNull::dangling());
+        // SAFETY: `data` is valid for `len` bytes until `arena` is dropped.
+        unsafe { SerializedData::from_raw_parts(arena, data, len) }
+    }
+
+    /// Encodes this message as compact JSON, using proto3 JSON field names.
+    ///
+    /// # Safety
+    /// `def` must be the def this message was created with, and be owned by a
+    /// live `DefPoolInner`.
+    pub unsafe fn to_json(&self, def: RawMessageDef) -> Result<String, String> {
+        let mut status = MaybeUninit::<upb_Status>::uninit();
+        // SAFETY: `upb_Status_Clear` initializes `status`. The first encode only
+        // measures the output; the second writes it plus a NUL terminator.
+        let len = unsafe {
+            upb_Status_Clear(status.as_mut_ptr());
+            let len = upb_JsonEncode(
+                self.msg,
+                def,
+                ptr::null(),
+                0,
+                ptr::null_mut(),
+                0,
+                status.as_mut_ptr(),
+            );
+            if !status.assume_init_ref().ok {
+                let msg = CStr::from_ptr(upb_Status_ErrorMessage(status.as_ptr()));
+                return Err(msg.to_string_lossy().into_owned());
+            }
+            len
+        };
+        let mut buf = vec![0u8; len + 1];
+        unsafe {
+            upb_JsonEncode(
+                self.msg,
+                def,
+                ptr::null(),
+                0,
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                status.as_mut_ptr(),
+            )
+        };
+        buf.truncate(len);
+        // upb only emits UTF-8 JSON.
+        Ok(String::from_utf8(buf).expect("upb_JsonEncode produced invalid UTF-8"))
+    }
+
+    /// Returns the value of the singular field named `name`, or `None` if
+    /// there is no such field or it is a repeated, map, or message field.
+    ///
+    /// # Safety
+    /// `def` must be the def this message was created with, and be owned by a
+    /// live `DefPoolInner`.
+    pub unsafe fn get(&self, def: RawMessageDef, name: &str) -> Option<DynamicValue<'_>> {
+        unsafe {
+            let field = find_singular_field(def, name)?;
+            to_dynamic_value(field, upb_Message_GetFieldByDef(self.msg, field))
+        }
+    }
+
+    /// Sets the singular field named `name`. Returns an error, leaving the
+    /// message unchanged, if there is no such field or `val` does not match
+    /// its type.
+    ///
+    /// # Safety
+    /// `def` must be the def this message was created with, and be owned by a
+    /// live `DefPoolInner`.
+    pub unsafe fn set(
+        &mut self,
+        def: RawMessageDef,
+        name: &str,
+        val: DynamicValue<'_>,
+    ) -> Result<(), FieldError> {
+        let field = unsafe {
+            upb_MessageDef_FindFieldByNameWithSize(def, name.as_ptr().cast(), name.len())
+        }
+        .ok_or(FieldError::NotFound)?;
+        if unsafe { upb_FieldDef_IsRepeated(field) } {
+            return Err(FieldError::WrongType);
+        }
+        let val = match (unsafe { upb_FieldDef_CType(field) }, val) {
+            (UpbCType::Bool, DynamicValue::Bool(v)) => upb_MessageValue { bool_val: v },
+            (UpbCType::Float, DynamicValue::F32(v)) => upb_MessageValue { float_val: v },
+            (UpbCType::Int32 | UpbCType::Enum, DynamicValue::I32(v)) => {
+                upb_MessageValue { int32_val: v }
+            }
+            (UpbCType::UInt32, DynamicValue::U32(v)) => upb_MessageValue { uint32_val: v },
+            (UpbCType::Double, DynamicValue::F64(v)) => upb_MessageValue { double_val: v },
+            (UpbCType::Int64, DynamicValue::I64(v)) => upb_MessageValue { int64_val: v },
+            (UpbCType::UInt64, DynamicValue::U64(v)) => upb_MessageValue { uint64_val: v },
+            (UpbCType::String, DynamicValue::String(v)) => {
+                upb_MessageValue { str_val: self.copy_into_arena(v.as_bytes()) }
+            }
+            (UpbCType::Bytes, DynamicValue::Bytes(v)) => {
+                upb_MessageValue { str_val: self.copy_into_arena(v) }
+            }
+            _ => return Err(FieldError::WrongType),
+        };
+        if !unsafe { upb_Message_SetFieldByDef(self.msg, field, val, self.arena.raw()) } {
+            panic!("upb_Message_SetFieldByDef failed");
+        }
+        Ok(())
+    }
+
+    /// Clears the repeated or map field named `name`.
+    ///
+    /// # Safety
+    /// `def` must be the def this message was created with, and be owned by a
+    /// live `DefPoolInner`.
+    pub unsafe fn clear_repeated(
+        &mut self,
+        def: RawMessageDef,
+        name: &str,
+    ) -> Result<(), FieldError> {
+        let field = unsafe {
+            upb_MessageDef_FindFieldByNameWithSize(def, name.as_ptr().cast(), name.len())
+        }
+        .ok_or(FieldError::NotFound)?;
+        if !unsafe { upb_FieldDef_IsRepeated(field) } {
+            return Err(FieldError::NotRepeated);
+        }
+        unsafe { upb_Message_ClearFieldByDef(self.msg, field) };
+        Ok(())
+    }
+
+    /// upb does not copy string data on set, so it must live on the arena.
+    fn copy_into_arena(&self, val: &[u8]) -> PtrAndLen {
+        if val.is_empty() {
+            return PtrAndLen { ptr: ptr::null(), len: 0 };
+        }
+        // SAFETY: the alignment of `[u8]` is less than `UPB_MALLOC_ALIGN`.
+        let new_alloc = unsafe { self.arena.alloc(Layout::for_value(val)) };
+        let start: *mut u8 = new_alloc.as_mut_ptr().cast();
+        // SAFETY: `new_alloc` is writeable for `val.len()` bytes.
+        unsafe { ptr::copy_nonoverlapping(val.as_ptr(), start, val.len()) };
+        PtrAndLen { ptr: start, len: val.len() }
+    }
+}
+
+/// Converts a value of the singular field `field`, or returns `None` for
+/// message fields.
+///
+/// # Safety
+/// `field` must be owned by a live `DefPoolInner`, and any string data in
+/// `val` must outlive `'a`.
+unsafe fn to_dynamic_value<'a>(
+    field: RawFieldDef,
+    val: upb_MessageValue,
+) -> Option<DynamicValue<'a>> {
+    unsafe {
+        Some(match upb_FieldDef_CType(field) {
+            UpbCType::Bool => DynamicValue::Bool(val.bool_val),
+            UpbCType::Float => DynamicValue::F32(val.float_val),
+            UpbCType::Int32 | UpbCType::Enum => DynamicValue::I32(val.int32_val),
+            UpbCType::UInt32 => DynamicValue::U32(val.uint32_val),
+            UpbCType::Double => DynamicValue::F64(val.double_val),
+            UpbCType::Int64 => DynamicValue::I64(val.int64_val),
+            UpbCType::UInt64 => DynamicValue::U64(val.uint64_val),
+            UpbCType::String => {
+                DynamicValue::String(ProtoStr::from_utf8_unchecked(val.str_val.as_ref()))
+            }
+            UpbCType::Bytes => DynamicValue::Bytes(val.str_val.as_ref()),
+            UpbCType::Message => return None,
+        })
+    }
+}
+
+/// # Safety
+/// `def` must be owned by a live `DefPoolInner`.
+unsafe fn find_singular_field(def: RawMessageDef, name: &str) -> Option<RawFieldDef> {
+    let field =
+        unsafe { upb_MessageDef_FindFieldByNameWithSize(def, name.as_ptr().cast(), name.len()) }?;
+    if unsafe { upb_FieldDef_IsRepeated(field) } {
+        return None;
+    }
+    Some(field)
+}
+
+extern "C" {
+    fn upb_Status_Clear(status: *mut upb_Status);
+    fn upb_Status_ErrorMessage(status: *const upb_Status) -> *const c_char;
+    fn upb_DefPool_New() -> Option<RawDefPool>;
+    fn upb_DefPool_Free(pool: RawDefPool);
+    fn upb_DefPool_AddSerializedFile(
+        pool: RawDefPool,
+        buf: *const c_char,
+        size: usize,
+        status: *mut upb_Status,
+    ) -> *const c_void;
+    fn upb_DefPool_FindMessageByNameWithSize(
+        pool: RawDefPool,
+        name: *const c_char,
+        len: usize,
+    ) -> Option<RawMessageDef>;
+    fn upb_MessageDef_FullName(def: RawMessageDef) -> *const c_char;
+    fn upb_MessageDef_FieldCount(def: RawMessageDef) -> std::ffi::c_int;
+    fn upb_MessageDef_FindFieldByNameWithSize(
+        def: RawMessageDef,
+        name: *const c_char,
+        len: usize,
+    ) -> Option<RawFieldDef>;
+    fn upb_MessageDef_MiniTable(def: RawMessageDef) -> *const c_void;
+    fn upb_FieldDef_CType(field: RawFieldDef) -> UpbCType;
+    fn upb_FieldDef_IsRepeated(field: RawFieldDef) -> bool;
+    fn upb_FieldDef_Default(field: RawFieldDef) -> upb_MessageValue;
+    fn upb_Message_New(mini_table: *const c_void, arena: RawArena) -> Option<RawMessage>;
+    fn upb_Message_GetFieldByDef(msg: RawMessage, field: RawFieldDef) -> upb_MessageValue;
+    fn upb_Message_SetFieldByDef(
+        msg: RawMessage,
+        field: RawFieldDef,
+        val: upb_MessageValue,
+        arena: RawArena,
+    ) -> bool;
+    fn upb_Message_ClearFieldByDef(msg: RawMessage, field: RawFieldDef);
+    fn upb_Message_AddUnknown(msg: RawMessage, data: *const c_char, len: usize, arena: RawArena);
+    fn upb_Decode(
+        buf: *const c_char,
+        size: usize,
+        msg: RawMessage,
+        mini_table: *const c_void,
+        extreg: *const c_void,
+        options: std::ffi::c_int,
+        arena: RawArena,
+    ) -> std::ffi::c_int;
+    fn upb_JsonEncode(
+        msg: RawMessage,
+        def: RawMessageDef,
+        ext_pool: *const c_void,
+        options: std::ffi::c_int,
+        buf: *mut c_char,
+        size: usize,
+        status: *mut upb_Status,
+    ) -> usize;
+    fn upb_Encode(
+        msg: RawMessage,
+        mini_table: *const c_void,
+        options: std::ffi::c_int,
+        arena: RawArena,
+        buf: *mut *mut u8,
+        size: *mut usize,
+    ) -> std::ffi::c_int;
 }
 
 #[cfg(test)]
@@ -653,6 +1580,118 @@ mod tests {
     use super::*;
     use googletest::prelude::*;
 
+    // Transcribed from google3/third_party/upb/upb/mem/alloc.h
+    #[repr(C)]
+    struct upb_alloc {
+        func: extern "C" fn(*const upb_alloc, *mut c_void, usize, usize) -> *mut c_void,
+    }
+
+    extern "C" {
+        fn upb_Arena_Init(mem: *mut u8, n: usize, alloc: *const upb_alloc) -> Option<RawArena>;
+    }
+
+    #[test]
+    fn arena_alloc_aligned() {
+        let arena = Arena::new();
+        // Misalign the arena's bump pointer relative to 16.
+        // SAFETY: `u64` is aligned to at most `UPB_MALLOC_ALIGN`.
+        unsafe { arena.alloc(Layout::new::<u64>()) };
+        for _ in 0..4 {
+            let block = arena.alloc_aligned(Layout::from_size_align(24, 16).unwrap());
+            assert_that!(block.len(), eq(24));
+            assert_that!(block.as_ptr() as usize % 16, eq(0));
+        }
+        let block = arena.alloc_aligned(Layout::new::<u32>());
+        assert_that!(block.len(), eq(4));
+    }
+
+    #[test]
+    fn arena_fuse_keeps_memory_alive() {
+        let target = Arena::new();
+        let ptr = {
+            let arena = Arena::new();
+            // SAFETY: `u64` is aligned to at most `UPB_MALLOC_ALIGN`.
+            let block = unsafe { arena.alloc(Layout::new::<u64>()) };
+            let ptr = block.as_mut_ptr().cast::<u64>();
+            // SAFETY: `block` is valid for writes of a `u64`.
+            unsafe { ptr.write(42) };
+            assert_that!(arena.fuse(&target), eq(true));
+            ptr
+        };
+        // SAFETY: `target` keeps the fused arena's blocks alive.
+        assert_that!(unsafe { ptr.read() }, eq(42));
+    }
+
+    #[test]
+    fn arena_scope() {
+        let entries = Arena::scope(|arena| {
+            let mut map = Map::<'_, i32, i32>::new(arena);
+            map.insert_from_slices(&[1, 2], &[10, 20]).unwrap();
+            map.iter().collect::<BTreeMap<_, _>>()
+        });
+        assert_that!(entries, eq(BTreeMap::from([(1, 10), (2, 20)])));
+    }
+
+    #[test]
+    fn arena_group_frees_on_drop() {
+        thread_local! {
+            static LIVE_BLOCKS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+        }
+        extern "C" fn counting_alloc(
+            _: *const upb_alloc,
+            ptr: *mut c_void,
+            _: usize,
+            size: usize,
+        ) -> *mut c_void {
+            if size == 0 {
+                LIVE_BLOCKS.with(|n| n.set(n.get() - 1));
+                // SAFETY: upb only frees blocks that this function allocated.
+                unsafe { free(ptr) };
+                return ptr::null_mut();
+            }
+            if ptr.is_null() {
+                LIVE_BLOCKS.with(|n| n.set(n.get() + 1));
+            }
+            // SAFETY: `ptr` is null or a block that this function allocated.
+            unsafe { realloc(ptr, size) }
+        }
+        static COUNTING_ALLOC: upb_alloc = upb_alloc { func: counting_alloc };
+        extern "C" {
+            fn realloc(ptr: *mut c_void, size: usize) -> *mut c_void;
+            fn free(ptr: *mut c_void);
+        }
+
+        let group = ArenaGroup::new();
+        for _ in 0..3 {
+            // SAFETY: without initial memory, every block comes from `COUNTING_ALLOC`.
+            let raw = unsafe { upb_Arena_Init(ptr::null_mut(), 0, &COUNTING_ALLOC) };
+            let arena = group.adopt(Arena { raw: raw.unwrap(), _not_sync: PhantomData });
+            // Larger than an arena's first block, so each arena needs more.
+            let mut arr = RepeatedField::<i64>::new(arena);
+            arr.reserve(1 << 12);
+        }
+        assert_that!(group.len(), eq(3));
+        // Nothing is freed while the group is alive.
+        assert_that!(LIVE_BLOCKS.with(|n| n.get()), ge(3));
+
+        drop(group);
+        assert_that!(LIVE_BLOCKS.with(|n| n.get()), eq(0));
+    }
+
+    #[test]
+    fn arena_group_new_arena() {
+        let group = ArenaGroup::new();
+        let a = group.new_arena();
+        let b = group.new_arena();
+        let mut arr_a = RepeatedField::<i32>::new(a);
+        let mut arr_b = RepeatedField::<i32>::new(b);
+        arr_a.push(1);
+        arr_b.push(2);
+        assert_that!(arr_a.as_slice(), eq([1]));
+        assert_that!(arr_b.as_slice(), eq([2]));
+        assert_that!(group.len(), eq(2));
+    }
+
     #[test]
     fn test_arena_new_and_free() {
         let arena = Arena::new();
@@ -673,6 +1712,8 @@ mod tests {
             )
         };
         assert_that!(&*serialized_data, eq(b"Hello world"));
+        assert_that!(serialized_data.slice(2..5), eq(Some(&b"llo"[..])));
+        assert_that!(serialized_data.slice(5..12), eq(None));
     }
 
     #[test]
@@ -689,7 +1730,272 @@ mod tests {
             arr.push(i);
             assert_that!(arr.get(arr.len() - 1), eq(Some(i)));
         }
+        assert_that!(arr.as_slice().len(), eq(2049));
+        assert_that!(arr.as_slice()[2048], eq(2047));
+    }
+
+    #[test]
+    fn array_as_slice() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<f64>::new(&arena);
+        assert_that!(arr.as_slice().len(), eq(0));
+        arr.push(1.5);
+        arr.push(-2.0);
+        assert_that!(arr.as_slice(), eq([1.5, -2.0]));
+
+        let mut arr = RepeatedField::<u64>::new(&arena);
+        arr.push(u64::MAX);
+        assert_that!(arr.as_slice(), eq([u64::MAX]));
+
+        // SAFETY: the empty array is only read.
+        let arr = RepeatedField::<i32>::from_inner(Private, unsafe { empty_array() });
+        assert_that!(arr.as_slice().len(), eq(0));
+    }
+
+    #[test]
+    fn owned_i32_array() {
+        let owned = OwnedRepeatedField::<i32>::new();
+        let mut arr = RepeatedField::<i32>::from_inner(Private, owned.as_inner());
+        arr.push(1);
+        arr.push(2);
+        assert_that!(arr.as_slice(), eq([1, 2]));
+    }
+
+    #[test]
+    fn i32_array_ref_into_iter() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        for i in 0..3 {
+            arr.push(i);
+        }
+        let mut iter = (&arr).into_iter();
+        assert_that!(iter.size_hint(), eq((3, Some(3))));
+        assert_that!(iter.next(), eq(Some(0)));
+        assert_that!(iter.size_hint(), eq((2, Some(2))));
+
+        let mut sum = 0;
+        for val in &arr {
+            sum += val;
+        }
+        assert_that!(sum, eq(3));
+    }
+
+    #[test]
+    fn i32_array_extend() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.extend_from_slice(&[]);
+        assert_that!(arr.len(), eq(0));
+        arr.extend_from_slice(&[1, 2]);
+        assert_that!(arr.as_slice(), eq([1, 2]));
+        arr.extend([3, 4]);
+        arr.extend_from_slice(&[5]);
+        assert_that!(arr.as_slice(), eq([1, 2, 3, 4, 5]));
+    }
+
+    #[test]
+    fn i32_array_swap_remove() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        assert_that!(arr.swap_remove(0), eq(None));
+        for i in 0..4 {
+            arr.push(i);
+        }
+        assert_that!(arr.swap_remove(4), eq(None));
+        assert_that!(arr.as_slice(), eq([0, 1, 2, 3]));
+        assert_that!(arr.swap_remove(1), eq(Some(1)));
+        assert_that!(arr.as_slice(), eq([0, 3, 2]));
+        assert_that!(arr.swap_remove(2), eq(Some(2)));
+        assert_that!(arr.as_slice(), eq([0, 3]));
+    }
+
+    #[test]
+    fn i32_array_remove() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        for i in 0..4 {
+            arr.push(i);
+        }
+        assert_that!(arr.remove(4), eq(None));
+        assert_that!(arr.remove(1), eq(Some(1)));
+        assert_that!(arr.as_slice(), eq([0, 2, 3]));
+        assert_that!(arr.remove(2), eq(Some(3)));
+        assert_that!(arr.as_slice(), eq([0, 2]));
+    }
+
+    #[test]
+    fn i32_array_insert() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.insert(0, 2);
+        arr.insert(0, 0);
+        arr.insert(1, 1);
+        arr.insert(3, 3);
+        assert_that!(arr.as_slice(), eq([0, 1, 2, 3]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn i32_array_insert_out_of_bounds() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.insert(1, 0);
+    }
+
+    #[test]
+    fn i32_array_splice() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.extend([1, 2, 3, 4]);
+        arr.splice(1..3, &[9, 9, 9]);
+        assert_that!(arr.as_slice(), eq([1, 9, 9, 9, 4]));
+        arr.splice(0..4, &[5]);
+        assert_that!(arr.as_slice(), eq([5, 4]));
+        arr.splice(2..2, &[6]);
+        assert_that!(arr.as_slice(), eq([5, 4, 6]));
+    }
+
+    #[test]
+    fn i32_array_retain() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.retain(|_| unreachable!());
+        arr.extend(1..=6);
+        arr.retain(|x| x % 2 == 1);
+        assert_that!(arr.as_slice(), eq([1, 3, 5]));
+        arr.retain(|_| false);
+        assert_that!(arr.len(), eq(0));
+    }
+
+    #[test]
+    fn i32_array_sort() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.sort();
+        arr.extend([3, -1, 2, -3, 1]);
+        arr.sort();
+        assert_that!(arr.as_slice(), eq([-3, -1, 1, 2, 3]));
+
+        // Only the keys are checked, since the order of equal keys is unspecified.
+        arr.sort_by(|a, b| a.abs().cmp(&b.abs()));
+        let keys: Vec<_> = arr.as_slice().iter().map(|x| x.abs()).collect();
+        assert_that!(keys, eq(vec![1, 1, 2, 3, 3]));
+    }
+
+    #[test]
+    fn f64_array_sort_by() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<f64>::new(&arena);
+        arr.extend([2.5, -0.5, 1.0]);
+        arr.sort_by(f64::total_cmp);
+        assert_that!(arr.as_slice(), eq([-0.5, 1.0, 2.5]));
+    }
+
+    #[test]
+    fn i32_array_reserve() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.reserve(100);
+        assert_that!(arr.capacity(), ge(100));
+        assert_that!(arr.len(), eq(0));
+
+        arr.push(1);
+        arr.reserve(200);
+        assert_that!(arr.capacity(), ge(201));
+        assert_that!(arr.get(0), eq(Some(1)));
+    }
+
+    #[test]
+    fn i32_array_shrink_to_fit() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.reserve(100);
+        arr.push(1);
+        arr.push(2);
+        arr.shrink_to_fit();
+        assert_that!(arr.capacity(), eq(2));
+        assert_that!(arr.as_slice(), eq([1, 2]));
+
+        arr.push(3);
+        assert_that!(arr.as_slice(), eq([1, 2, 3]));
+    }
+
+    #[test]
+    fn i32_array_clear() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.clear();
+        assert_that!(arr.len(), eq(0));
+
+        arr.push(1);
+        arr.push(2);
+        let capacity = arr.capacity();
+        arr.clear();
+        assert_that!(arr.len(), eq(0));
+        assert_that!(arr.capacity(), eq(capacity));
+
+        arr.push(3);
+        assert_that!(arr.as_slice(), eq([3]));
+    }
+
+    #[test]
+    fn i32_array_truncate() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        for i in 0..4 {
+            arr.push(i);
+        }
+        arr.truncate(5);
+        assert_that!(arr.as_slice(), eq([0, 1, 2, 3]));
+        arr.truncate(4);
+        assert_that!(arr.as_slice(), eq([0, 1, 2, 3]));
+        arr.truncate(2);
+        assert_that!(arr.as_slice(), eq([0, 1]));
+        arr.truncate(0);
+        assert_that!(arr.len(), eq(0));
     }
+
+    #[test]
+    fn i32_array_clone_from_slice() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        for _ in 0..3 {
+            arr.push(0);
+        }
+        arr.clone_from_slice(&[1, 2, 3]);
+        assert_that!(arr.len(), eq(3));
+        assert_that!(arr.as_mut_slice(), eq([1, 2, 3]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn i32_array_clone_from_slice_len_mismatch() {
+        let arena = Arena::new();
+        let mut arr = RepeatedField::<i32>::new(&arena);
+        arr.push(0);
+        arr.clone_from_slice(&[1, 2]);
+    }
+
+    #[test]
+    fn bool_array_copy_from() {
+        let arena = Arena::new();
+        let mut src = RepeatedField::<bool>::new(&arena);
+        for val in [true, false, true] {
+            src.push(val);
+        }
+        let mut dst = RepeatedField::<bool>::new(&arena);
+        for _ in 0..5 {
+            dst.push(true);
+        }
+        dst.copy_from(&src);
+        assert_that!(dst.as_slice(), eq([true, false, true]));
+
+        // Growing past the destination's old length stays within `src`.
+        let mut empty = RepeatedField::<bool>::new(&arena);
+        empty.copy_from(&src);
+        assert_that!(empty.as_slice(), eq([true, false, true]));
+    }
+
     #[test]
     fn u32_array() {
         let mut arena = Arena::new();
@@ -727,6 +2033,163 @@ mod tests {
         assert_that!(map.len(), eq(0));
     }
 
+    #[test]
+    fn i32_i32_map_count_matching() {
+        let arena = Arena::new();
+        let mut map = Map::<'_, i32, i32>::new(&arena);
+        assert_that!(map.count_matching(|_, _| true), eq(0));
+
+        for i in 1..=5 {
+            assert_that!(map.insert(i, i * 10), eq(true));
+        }
+        assert_that!(map.count_matching(|k, _| k % 2 == 0), eq(2));
+        assert_that!(map.count_matching(|_, &v| v > 20), eq(3));
+    }
+
+    #[test]
+    fn i32_i32_map_iter() {
+        let arena = Arena::new();
+        let mut map = Map::<'_, i32, i32>::new(&arena);
+        assert_that!(map.iter().count(), eq(0));
+        map.insert(1, 10);
+        map.insert(2, 20);
+        assert_that!(map.contains_key(1), eq(true));
+        assert_that!(map.contains_key(3), eq(false));
+
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort();
+        assert_that!(entries, eq(vec![(1, 10), (2, 20)]));
+    }
+
+    #[test]
+    fn i32_i32_map_try_insert() {
+        extern "C" fn fail_alloc(
+            _: *const upb_alloc,
+            _: *mut c_void,
+            _: usize,
+            _: usize,
+        ) -> *mut c_void {
+            ptr::null_mut()
+        }
+        static FAILING_ALLOC: upb_alloc = upb_alloc { func: fail_alloc };
+
+        let mut mem = [0u64; 128];
+        // SAFETY: the block allocator always fails, so the arena can only
+        // allocate from `mem`, which outlives it.
+        let raw = unsafe {
+            upb_Arena_Init(mem.as_mut_ptr().cast(), std::mem::size_of_val(&mem), &FAILING_ALLOC)
+        };
+        let arena = Arena { raw: raw.unwrap(), _not_sync: PhantomData };
+        let mut map = Map::<'_, i32, i32>::new(&arena);
+
+        assert_that!(map.try_insert(0, 1), eq(Ok(true)));
+        assert_that!(map.try_insert(0, 2), eq(Ok(false)));
+        assert_that!(map.get(0), eq(Some(2)));
+
+        let mut key = 1;
+        let err = loop {
+            match map.try_insert(key, key) {
+                Ok(inserted) => assert_that!(inserted, eq(true)),
+                Err(err) => break err,
+            }
+            key += 1;
+            assert!(key < 1000, "capped arena never ran out of memory");
+        };
+        assert_that!(err, eq(AllocError));
+        drop(arena);
+    }
+
+    #[test]
+    fn i32_i32_map_to_btree_map() {
+        let arena = Arena::new();
+        let mut map = Map::<'_, i32, i32>::new(&arena);
+        for k in [3, -1, 7, 0, 5] {
+            map.insert(k, k * 10);
+        }
+        let ordered: Vec<_> = map.to_btree_map().into_iter().collect();
+        assert_that!(ordered, eq(vec![(-1, -10), (0, 0), (3, 30), (5, 50), (7, 70)]));
+    }
+
+    #[test]
+    fn i32_i32_map_insert_from_slices() {
+        let arena = Arena::new();
+        let mut map = Map::<'_, i32, i32>::new(&arena);
+        assert_that!(map.insert_from_slices(&[1, 2, 3], &[10, 20, 30]), eq(Ok(())));
+        assert_that!(
+            map.to_btree_map().into_iter().collect::<Vec<_>>(),
+            eq(vec![(1, 10), (2, 20), (3, 30)])
+        );
+        assert_that!(map.insert_from_slices(&[4, 5], &[40]), eq(Err(LengthMismatch)));
+        assert_that!(map.len(), eq(3));
+    }
+
+    #[test]
+    fn i32_i32_map_shrink_to_fit() {
+        let arena = Arena::new();
+        let mut map = Map::<'_, i32, i32>::new(&arena);
+        for k in 0..1000 {
+            map.insert(k, k * 10);
+        }
+        for k in 3..1000 {
+            map.remove(k);
+        }
+        map.shrink_to_fit();
+        assert_that!(map.len(), eq(3));
+        assert_that!(
+            map.to_btree_map().into_iter().collect::<Vec<_>>(),
+            eq(vec![(0, 0), (1, 10), (2, 20)])
+        );
+        map.insert(3, 30);
+        assert_that!(map.get(3), eq(Some(30)));
+    }
+
+    #[test]
+    fn i32_i32_map_get_or() {
+        let arena = Arena::new();
+        let mut map = Map::<'_, i32, i32>::new(&arena);
+        map.insert(1, 10);
+        assert_that!(map.get_or(1, -1), eq(10));
+        assert_that!(map.get_or(2, -1), eq(-1));
+    }
+
+    #[test]
+    fn i32_i32_map_merge_from() {
+        let arena = Arena::new();
+        let mut map = Map::<'_, i32, i32>::new(&arena);
+        map.insert(1, 10);
+        map.insert(2, 99);
+
+        let other_arena = Arena::new();
+        let mut other = Map::<'_, i32, i32>::new(&other_arena);
+        other.insert(2, 20);
+        other.insert(3, 30);
+
+        map.merge_from(&other);
+        drop(other_arena);
+        assert_that!(map.len(), eq(3));
+        assert_that!(map.get(1), eq(Some(10)));
+        assert_that!(map.get(2), eq(Some(20)));
+        assert_that!(map.get(3), eq(Some(30)));
+    }
+
+    #[test]
+    fn str_i32_map() {
+        let arena = Arena::new();
+        let mut map = Map::<'_, ProtoStr, i32>::new(&arena);
+        assert_that!(map.len(), eq(0));
+
+        let key = String::from("one");
+        assert_that!(map.insert(&key, 1), eq(true));
+        drop(key);
+        assert_that!(map.get("one"), eq(Some(1)));
+        assert_that!(map.get("two"), eq(None));
+        assert_that!(map.len(), eq(1));
+
+        assert_that!(map.remove("one"), eq(Some(1)));
+        assert_that!(map.len(), eq(0));
+        assert_that!(map.remove("one"), eq(None));
+    }
+
     #[test]
     fn i64_f64_map() {
         let arena = Arena::new();
@@ -9,5 +9,67 @@
 #define UPB_BUILD_API
 
 #include "upb/collections/map.h"  // IWYU pragma: keep
+#include "upb/base/internal/log2.h"
 #include "upb/collections/array.h"  // IWYU pragma: keep
 #include "upb/mem/arena.h"          // IWYU pragma: keep
+#include "upb/message/internal/array.h"
+#include "upb/message/internal/map.h"
+#include "upb/reflection/def.h"
+
+// Must be last.
+#include "upb/port/def.inc"
+
+size_t upb_Array_Capacity(const upb_Array* arr) { return arr->capacity; }
+
+bool upb_Array_Reserve(upb_Array* arr, size_t size, upb_Arena* arena) {
+  return _upb_array_reserve(arr, size, arena);
+}
+
+size_t upb_Array_ElementSize(const upb_Array* arr) {
+  return (size_t)1 << _upb_Array_ElementSizeLg2(arr);
+}
+
+// Shrinking never fails: the arena either gives back the tail of its most
+// recent allocation or leaves the buffer where it is.
+void upb_Array_ShrinkToFit(upb_Array* arr, upb_Arena* arena) {
+  const size_t lg2 = _upb_Array_ElementSizeLg2(arr);
+  void* ptr = upb_Arena_Realloc(arena, _upb_array_ptr(arr),
+                                arr->capacity << lg2, arr->size << lg2);
+  arr->data = _upb_tag_arrptr(ptr, lg2);
+  arr->capacity = arr->size;
+}
+
+// Rehashes `map` into the smallest table that keeps it under the max load
+// factor. The old table stays on the arena until it is freed. On allocation
+// failure the map is left unchanged.
+void upb_Map_ShrinkToFit(upb_Map* map, upb_Arena* arena) {
+  // Same sizing as `upb_strtable_init`.
+  size_t need_entries = (upb_strtable_count(&map->table) + 1) * 1204 / 1024;
+  int size_lg2 = upb_Log2Ceiling(need_entries);
+  if (size_lg2 >= map->table.t.size_lg2) return;
+  upb_strtable_resize(&map->table, size_lg2, arena);
+}
+
+// Parses a serialized FileDescriptorProto and adds it to `pool`. The pool
+// copies everything it needs, so the parsed proto is freed before returning.
+const upb_FileDef* upb_DefPool_AddSerializedFile(upb_DefPool* pool,
+                                                 const char* buf, size_t size,
+                                                 upb_Status* status) {
+  upb_Arena* arena = upb_Arena_New();
+  if (!arena) {
+    upb_Status_SetErrorMessage(status, "out of memory");
+    return NULL;
+  }
+  const UPB_DESC(FileDescriptorProto)* proto =
+      UPB_DESC(FileDescriptorProto_parse)(buf, size, arena);
+  const upb_FileDef* file = NULL;
+  if (proto) {
+    file = upb_DefPool_AddFile(pool, proto, status);
+  } else {
+    upb_Status_SetErrorMessage(status, "failed to parse FileDescriptorProto");
+  }
+  upb_Arena_Free(arena);
+  return file;
+}
+
+#include "upb/port/undef.inc"
@@ -6,7 +6,10 @@
 // https://developers.google.com/open-source/licenses/bsd
 
 use crate::__internal::{Private, PtrAndLen, RawMessage};
-use crate::__runtime::{copy_bytes_in_arena_if_needed_by_runtime, MutatorMessageRef};
+use crate::__runtime::{
+    concat_bytes_in_arena_if_needed_by_runtime, copy_bytes_in_arena_if_needed_by_runtime,
+    MutatorMessageRef,
+};
 use crate::{
     AbsentField, FieldEntry, Mut, MutProxy, Optional, PresentField, Proxied, ProxiedWithPresence,
     View, ViewProxy,
@@ -380,6 +383,20 @@ impl<'msg> RawVTableMutator<'msg, [u8]> {
         unsafe { (self.vtable.setter)(self.msg_ref.msg(), val.into()) }
     }
 
+    /// Sets the field to its current value followed by `more`.
+    ///
+    /// # Safety
+    /// - `msg_ref` must be valid for `'msg`
+    /// - If this is for a `string` field, `more` must be valid UTF-8 if the
+    ///   runtime requires it.
+    pub(crate) unsafe fn append(self, more: &[u8]) {
+        let val = concat_bytes_in_arena_if_needed_by_runtime(self.msg_ref, self.get(), more);
+        // SAFETY:
+        // - `msg_ref` is valid for `'msg` as promised by the caller of `new`.
+        // - `val` is in the message's arena if the runtime requires it.
+        unsafe { (self.vtable.setter)(self.msg_ref.msg(), (&*val).into()) }
+    }
+
     pub(crate) fn truncate(&self, len: usize) {
         if len == 0 {
             // SAFETY: The empty string is valid UTF-8.
@@ -0,0 +1,99 @@
+// Protocol Buffers - Google's data interchange format
+// Copyright 2023 Google LLC.  All rights reserved.
+//
+// Use of this source code is governed by a BSD-style
+// license that can be found in the LICENSE file or at
+// https://developers.google.com/open-source/licenses/bsd
+
+//! Encoding of single fields in the protobuf wire format, for injecting raw
+//! unknown fields into messages.
+
+use crate::__internal::Private;
+
+/// How a field value is encoded on the wire. Groups are not supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireType {
+    /// `bytes` is a single varint.
+    Varint = 0,
+    /// `bytes` is 8 little-endian bytes.
+    Fixed64 = 1,
+    /// `bytes` is the payload, without its length prefix.
+    LengthDelimited = 2,
+    /// `bytes` is 4 little-endian bytes.
+    Fixed32 = 5,
+}
+
+const MAX_FIELD_NUMBER: i32 = (1 << 29) - 1;
+
+/// Encodes the tag for `number` and `wire_type`, followed by `bytes`.
+///
+/// # Panics
+/// Panics if `number` is not a valid field number or `bytes` is not a valid
+/// value for `wire_type`.
+pub fn encode_field(_private: Private, number: i32, wire_type: WireType, bytes: &[u8]) -> Vec<u8> {
+    assert!((1..=MAX_FIELD_NUMBER).contains(&number), "invalid field number {number}");
+    let valid = match wire_type {
+        WireType::Varint => is_single_varint(bytes),
+        WireType::Fixed64 => bytes.len() == 8,
+        WireType::LengthDelimited => true,
+        WireType::Fixed32 => bytes.len() == 4,
+    };
+    assert!(valid, "{bytes:?} is not a valid {wire_type:?} value");
+
+    let mut out = Vec::with_capacity(bytes.len() + 15);
+    write_varint(&mut out, (number as u64) << 3 | wire_type as u64);
+    if wire_type == WireType::LengthDelimited {
+        write_varint(&mut out, bytes.len() as u64);
+    }
+    out.extend_from_slice(bytes);
+    out
+}
+
+fn write_varint(out: &mut Vec<u8>, mut val: u64) {
+    while val >= 0x80 {
+        out.push(val as u8 | 0x80);
+        val >>= 7;
+    }
+    out.push(val as u8);
+}
+
+fn is_single_varint(bytes: &[u8]) -> bool {
+    match bytes.split_last() {
+        Some((last, rest)) => {
+            bytes.len() <= 10 && last & 0x80 == 0 && rest.iter().all(|b| b & 0x80 != 0)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_each_wire_type() {
+        assert_eq!(encode_field(Private, 1, WireType::Varint, b"\x96\x01"), b"\x08\x96\x01");
+        assert_eq!(encode_field(Private, 2, WireType::Fixed64, &[0; 8]), b"\x11\0\0\0\0\0\0\0\0");
+        assert_eq!(encode_field(Private, 3, WireType::LengthDelimited, b"hi"), b"\x1a\x02hi");
+        assert_eq!(encode_field(Private, 4, WireType::Fixed32, &[1, 0, 0, 0]), b"\x25\x01\0\0\0");
+        assert_eq!(encode_field(Private, 1000, WireType::Varint, b"\x01"), b"\xc0\x3e\x01");
+    }
+
+    #[test]
+    #[should_panic]
+    fn truncated_varint() {
+        encode_field(Private, 1, WireType::Varint, b"\x96");
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_fixed_len() {
+        encode_field(Private, 1, WireType::Fixed32, &[0; 8]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_field_number() {
+        encode_field(Private, 0, WireType::Varint, b"\x01");
+    }
+}
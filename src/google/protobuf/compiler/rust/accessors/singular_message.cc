@@ -58,6 +58,7 @@ void SingularMessage::InExternC(Context<FieldDescriptor> field) const {
   field.Emit(
       {
           {"getter_thunk", Thunk(field, "get")},
+          {"hazzer_thunk", Thunk(field, "has")},
           {"ReturnType",
            [&] {
              if (field.is_cpp()) {
@@ -72,6 +73,7 @@ void SingularMessage::InExternC(Context<FieldDescriptor> field) const {
       },
       R"rs(
                   fn $getter_thunk$(raw_msg: $pbi$::RawMessage) -> $ReturnType$;
+                  fn $hazzer_thunk$(raw_msg: $pbi$::RawMessage) -> bool;
                )rs");
 }
 
@@ -79,11 +81,15 @@ void SingularMessage::InThunkCc(Context<FieldDescriptor> field) const {
   field.Emit({{"QualifiedMsg",
                cpp::QualifiedClassName(field.desc().containing_type())},
               {"getter_thunk", Thunk(field, "get")},
+              {"hazzer_thunk", Thunk(field, "has")},
               {"field", cpp::FieldName(&field.desc())}},
              R"cc(
                const void* $getter_thunk$($QualifiedMsg$* msg) {
                  return static_cast<const void*>(&msg->$field$());
                }
+               bool $hazzer_thunk$($QualifiedMsg$* msg) {
+                 return msg->has_$field$();
+               }
              )cc");
 }
 
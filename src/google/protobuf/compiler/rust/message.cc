@@ -71,6 +71,32 @@ void MessageSerialize(Context<Descriptor> msg) {
   ABSL_LOG(FATAL) << "unreachable";
 }
 
+void MessageCanonicalSerialize(Context<Descriptor> msg) {
+  switch (msg.opts().kernel) {
+    case Kernel::kCpp:
+      msg.Emit(
+          {{"canonical_serialize_thunk", Thunk(msg, "canonical_serialize")}},
+          R"rs(
+            unsafe { $canonical_serialize_thunk$(self.inner.msg) }
+          )rs");
+      return;
+
+    case Kernel::kUpb:
+      msg.Emit({{"serialize_ex_thunk", Thunk(msg, "serialize_ex")}}, R"rs(
+        let arena = $pbr$::Arena::new();
+        let mut len = 0;
+        unsafe {
+          let data = $serialize_ex_thunk$(
+            self.inner.msg, $pbr$::ENCODE_DETERMINISTIC, arena.raw(), &mut len);
+          $pbr$::SerializedData::from_raw_parts(arena, data, len)
+        }
+      )rs");
+      return;
+  }
+
+  ABSL_LOG(FATAL) << "unreachable";
+}
+
 void MessageDeserialize(Context<Descriptor> msg) {
   switch (msg.opts().kernel) {
     case Kernel::kCpp:
@@ -115,6 +141,32 @@ void MessageDeserialize(Context<Descriptor> msg) {
   ABSL_LOG(FATAL) << "unreachable";
 }
 
+void MessageAddUnknown(Context<Descriptor> msg) {
+  switch (msg.opts().kernel) {
+    case Kernel::kCpp:
+      msg.Emit({{"add_unknown_thunk", Thunk(msg, "add_unknown")}}, R"rs(
+        let success = unsafe {
+          let data = $pbr$::SerializedData::from_raw_parts(
+            $NonNull$::new(data.as_ptr() as *mut _).unwrap(),
+            data.len(),
+          );
+
+          $add_unknown_thunk$(self.inner.msg, data)
+        };
+        assert!(success, "failed to parse encoded unknown field");
+      )rs");
+      return;
+
+    case Kernel::kUpb:
+      msg.Emit(R"rs(
+        self.inner.add_unknown(&data);
+      )rs");
+      return;
+  }
+
+  ABSL_LOG(FATAL) << "unreachable";
+}
+
 void MessageExterns(Context<Descriptor> msg) {
   switch (msg.opts().kernel) {
     case Kernel::kCpp:
@@ -123,13 +175,17 @@ void MessageExterns(Context<Descriptor> msg) {
               {"new_thunk", Thunk(msg, "new")},
               {"delete_thunk", Thunk(msg, "delete")},
               {"serialize_thunk", Thunk(msg, "serialize")},
+              {"canonical_serialize_thunk", Thunk(msg, "canonical_serialize")},
               {"deserialize_thunk", Thunk(msg, "deserialize")},
+              {"add_unknown_thunk", Thunk(msg, "add_unknown")},
           },
           R"rs(
           fn $new_thunk$() -> $pbi$::RawMessage;
           fn $delete_thunk$(raw_msg: $pbi$::RawMessage);
           fn $serialize_thunk$(raw_msg: $pbi$::RawMessage) -> $pbr$::SerializedData;
+          fn $canonical_serialize_thunk$(raw_msg: $pbi$::RawMessage) -> $pbr$::SerializedData;
           fn $deserialize_thunk$(raw_msg: $pbi$::RawMessage, data: $pbr$::SerializedData) -> bool;
+          fn $add_unknown_thunk$(raw_msg: $pbi$::RawMessage, data: $pbr$::SerializedData) -> bool;
         )rs");
       return;
 
@@ -138,11 +194,13 @@ void MessageExterns(Context<Descriptor> msg) {
           {
               {"new_thunk", Thunk(msg, "new")},
               {"serialize_thunk", Thunk(msg, "serialize")},
+              {"serialize_ex_thunk", Thunk(msg, "serialize_ex")},
               {"deserialize_thunk", Thunk(msg, "parse")},
           },
           R"rs(
           fn $new_thunk$(arena: $pbi$::RawArena) -> $pbi$::RawMessage;
           fn $serialize_thunk$(msg: $pbi$::RawMessage, arena: $pbi$::RawArena, len: &mut usize) -> $NonNull$<u8>;
+          fn $serialize_ex_thunk$(msg: $pbi$::RawMessage, options: i32, arena: $pbi$::RawArena, len: &mut usize) -> $NonNull$<u8>;
           fn $deserialize_thunk$(data: *const u8, size: usize, arena: $pbi$::RawArena) -> Option<$pbi$::RawMessage>;
       )rs");
       return;
@@ -206,7 +264,9 @@ void GenerateRs(Context<Descriptor> msg) {
           {"Msg", msg.desc().name()},
           {"Msg::new", [&] { MessageNew(msg); }},
           {"Msg::serialize", [&] { MessageSerialize(msg); }},
+          {"Msg::canonical_serialize", [&] { MessageCanonicalSerialize(msg); }},
           {"Msg::deserialize", [&] { MessageDeserialize(msg); }},
+          {"Msg::add_unknown", [&] { MessageAddUnknown(msg); }},
           {"Msg::drop", [&] { MessageDrop(msg); }},
           {"Msg_externs", [&] { MessageExterns(msg); }},
           {"accessor_fns",
@@ -228,6 +288,94 @@ void GenerateRs(Context<Descriptor> msg) {
                msg.printer().PrintRaw("\n");
              }
            }},
+          {"present_field_checks",
+           [&] {
+             for (int i = 0; i < msg.desc().field_count(); ++i) {
+               const FieldDescriptor& field = *msg.desc().field(i);
+               // Only fields with a generated accessor expose a hazzer thunk,
+               // which excludes enum, group, and `ctype` fields as well as
+               // messages defined in another crate.
+               if (field.is_repeated() || !field.has_presence() ||
+                   field.options().has_ctype() ||
+                   field.type() == FieldDescriptor::TYPE_GROUP ||
+                   field.type() == FieldDescriptor::TYPE_ENUM) {
+                 continue;
+               }
+               if (field.type() == FieldDescriptor::TYPE_MESSAGE &&
+                   !msg.generator_context().is_file_in_current_crate(
+                       field.message_type()->file())) {
+                 continue;
+               }
+               msg.Emit(
+                   {{"hazzer_thunk", Thunk(msg.WithDesc(field), "has")},
+                    {"number", std::to_string(field.number())}},
+                   R"rs(
+                     if unsafe { $hazzer_thunk$(self.inner.msg) } {
+                       numbers.push($number$);
+                     }
+                   )rs");
+             }
+           }},
+          {"field_infos",
+           [&] {
+             for (int i = 0; i < msg.desc().field_count(); ++i) {
+               const FieldDescriptor& field = *msg.desc().field(i);
+               absl::string_view kind;
+               if (field.is_map()) {
+                 kind = "Map";
+               } else {
+                 switch (field.type()) {
+                   case FieldDescriptor::TYPE_MESSAGE:
+                   case FieldDescriptor::TYPE_GROUP:
+                     kind = "Message";
+                     break;
+                   case FieldDescriptor::TYPE_ENUM:
+                     kind = "Enum";
+                     break;
+                   case FieldDescriptor::TYPE_STRING:
+                     kind = "String";
+                     break;
+                   case FieldDescriptor::TYPE_BYTES:
+                     kind = "Bytes";
+                     break;
+                   default:
+                     kind = "Scalar";
+                     break;
+                 }
+               }
+               msg.Emit(
+                   {{"number", std::to_string(field.number())},
+                    {"name", field.name()},
+                    {"kind", kind},
+                    {"repeated",
+                     field.is_repeated() && !field.is_map() ? "true" : "false"},
+                    {"in_oneof",
+                     field.real_containing_oneof() != nullptr ? "true"
+                                                              : "false"}},
+                   R"rs(
+                     $pb$::FieldInfo {
+                       number: $number$,
+                       name: "$name$",
+                       kind: $pb$::FieldKind::$kind$,
+                       repeated: $repeated$,
+                       in_oneof: $in_oneof$,
+                     },
+                   )rs");
+             }
+           }},
+          {"oneof_clear_by_name_cases",
+           [&] {
+             for (int i = 0; i < msg.desc().real_oneof_decl_count(); ++i) {
+               msg.Emit(
+                   {{"oneof_name", msg.desc().real_oneof_decl(i)->name()}},
+                   R"rs(
+                     "$oneof_name$" => {
+                       self.clear_$oneof_name$();
+                       true
+                     }
+                   )rs");
+             }
+           }},
           {"accessor_externs",
            [&] {
              for (int i = 0; i < msg.desc().field_count(); ++i) {
@@ -384,6 +532,9 @@ void GenerateRs(Context<Descriptor> msg) {
             $Msg::new$
           }
 
+          /// Serializes the message to bytes. The encoded size is not cached
+          /// between calls: upb computes it anew while encoding, so use the
+          /// `len()` of the result rather than serializing again to measure.
           pub fn serialize(&self) -> $pbr$::SerializedData {
             $Msg::serialize$
           }
@@ -391,11 +542,81 @@ void GenerateRs(Context<Descriptor> msg) {
             $Msg::deserialize$
           }
 
+          /// Serializes the message to bytes that only depend on its
+          /// contents: fields are written in field number order and map
+          /// entries are sorted by key. Useful for signing or as a cache key.
+          ///
+          /// Unknown fields are written last, in the order they were added.
+          pub fn canonical_bytes(&self) -> $pbr$::SerializedData {
+            $Msg::canonical_serialize$
+          }
+
+          /// Appends a field to this message's unknown fields, as if it had
+          /// been parsed from a newer version of the schema. It is kept when
+          /// the message is serialized.
+          ///
+          /// # Panics
+          /// Panics if `number` is not a valid field number or `bytes` is not
+          /// a valid value for `wire_type`.
+          pub fn add_unknown_field(
+            &mut self,
+            number: i32,
+            wire_type: $pb$::WireType,
+            bytes: &[u8],
+          ) {
+            let data = $pbi$::encode_field($pbi$::Private, number, wire_type, bytes);
+            $Msg::add_unknown$
+          }
+
+          /// Parses consecutive length-delimited messages from `reader` until
+          /// it is exhausted. A message cut off by the end of input is an error.
+          pub fn parse_delimited_stream<R: $std$::io::Read>(reader: R)
+            -> impl $std$::iter::Iterator<Item = Result<Self, $pb$::ParseError>> {
+            $pbi$::DelimitedFrames::new($pbi$::Private, reader).map(|frame| {
+              let mut msg = Self::new();
+              msg.deserialize(&frame?)?;
+              Ok(msg)
+            })
+          }
+
           $accessor_fns$
 
           $oneof_accessor_fns$
+
+          /// Returns the numbers of the fields with explicit presence that are
+          /// currently set. Enum, group, and `[ctype]` fields, and message
+          /// fields whose type is defined in another crate, are not reported.
+          pub fn present_fields(&self) -> $pb$::PresenceSet {
+            #[allow(unused_mut)]
+            let mut numbers = $std$::vec::Vec::new();
+            $present_field_checks$
+            $pb$::PresenceSet::from_numbers($pbi$::Private, numbers)
+          }
+
+          /// Describes the fields of this message type, in declaration order.
+          pub fn fields(&self) -> impl $std$::iter::Iterator<Item = $pb$::FieldInfo> {
+            const FIELDS: &[$pb$::FieldInfo] = &[
+              $field_infos$
+            ];
+            FIELDS.iter().copied()
+          }
+
+          /// Clears the oneof declared as `name`, returning `false` if this
+          /// message has no oneof with that name.
+          pub fn clear_oneof_by_name(&mut self, name: &str) -> bool {
+            match name {
+              $oneof_clear_by_name_cases$
+              _ => false,
+            }
+          }
         }  // impl $Msg$
 
+        impl $std$::convert::From<&$Msg$> for $std$::vec::Vec<u8> {
+          fn from(msg: &$Msg$) -> Self {
+            msg.serialize().to_vec()
+          }
+        }
+
         //~ We implement drop unconditionally, so that `$Msg$: Drop` regardless
         //~ of kernel.
         impl $std$::ops::Drop for $Msg$ {
@@ -428,6 +649,25 @@ void GenerateRs(Context<Descriptor> msg) {
       }
     )rs");
   }
+
+  if (msg.is_upb()) {
+    msg.printer().PrintRaw("\n");
+    msg.Emit({{"Msg", msg.desc().name()}}, R"rs(
+      impl $Msg$ {
+        /// Hands this message's memory over to `target` by fusing their
+        /// arenas, and returns a view of it that stays valid for as long as
+        /// `target` lives.
+        ///
+        /// # Panics
+        /// Panics if `target` was created over a caller-provided initial
+        /// block and can't be fused.
+        pub fn adopt_into_arena(self, target: &$pbr$::Arena) -> $Msg$View<'_> {
+          assert!(self.inner.arena.fuse(target), "arena can't be fused");
+          $Msg$View::new($pbi$::Private, self.inner.msg)
+        }
+      }
+    )rs");
+  }
 }
 
 // Generates code for a particular message in `.pb.thunk.cc`.
@@ -445,7 +685,9 @@ void GenerateThunksCc(Context<Descriptor> msg) {
        {"new_thunk", Thunk(msg, "new")},
        {"delete_thunk", Thunk(msg, "delete")},
        {"serialize_thunk", Thunk(msg, "serialize")},
+       {"canonical_serialize_thunk", Thunk(msg, "canonical_serialize")},
        {"deserialize_thunk", Thunk(msg, "deserialize")},
+       {"add_unknown_thunk", Thunk(msg, "add_unknown")},
        {"nested_msg_thunks",
         [&] {
           for (int i = 0; i < msg.desc().nested_type_count(); ++i) {
@@ -477,10 +719,21 @@ void GenerateThunksCc(Context<Descriptor> msg) {
         google::protobuf::rust_internal::SerializedData $serialize_thunk$($QualifiedMsg$* msg) {
           return google::protobuf::rust_internal::SerializeMsg(msg);
         }
+        google::protobuf::rust_internal::SerializedData $canonical_serialize_thunk$(
+            $QualifiedMsg$* msg) {
+          return google::protobuf::rust_internal::SerializeMsgDeterministic(msg);
+        }
         bool $deserialize_thunk$($QualifiedMsg$* msg,
                                  google::protobuf::rust_internal::SerializedData data) {
           return msg->ParseFromArray(data.data, data.len);
         }
+        bool $add_unknown_thunk$($QualifiedMsg$* msg,
+                                 google::protobuf::rust_internal::SerializedData data) {
+          google::protobuf::UnknownFieldSet fields;
+          if (!fields.ParseFromArray(data.data, data.len)) return false;
+          msg->mutable_unknown_fields()->MergeFrom(fields);
+          return true;
+        }
 
         $accessor_thunks$
 
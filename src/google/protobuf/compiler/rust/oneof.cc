@@ -20,17 +20,23 @@ namespace protobuf {
 namespace compiler {
 namespace rust {
 
-// We emit three Rust enums:
+// We emit four Rust enums:
 // -  An enum acting as a tagged union that has each case holds a View<> of
 //    each of the cases. Named as the one_of name in CamelCase.
 // -  An enum acting as a tagged union that has each case holds a Mut<> of
 //    each of the cases. Named as one_of name in CamelCase with "Mut" appended.
 //    [TODO: Mut not implemented yet].
+// -  An enum that has each case hold an owned copy of the field value. Named
+//    as the one_of name in CamelCase with "Owned" appended. This is returned
+//    by `take_<oneof>()`, which also clears the oneof, and is `From` the view
+//    enum. String cases are converted lossily, like
+//    `ProtoStr::to_cow_lossy`. Message and enum cases have no owned variant
+//    yet: `take_<oneof>()` leaves them set and both return `not_set`.
 // -  A simple enum whose cases have int values matching the cpp or upb's
 //    case enum. Named as the one_of camelcase with "Case" appended.
-// All three contain cases matching the fields in the oneof CamelCased.
-// The first and second are exposed in the API, the third is internal and
-// used for interop with the Kernels in the generation of the other two.
+// All four contain cases matching the fields in the oneof CamelCased.
+// The first three are exposed in the API, the last is internal and used for
+// interop with the Kernels in the generation of the other three.
 //
 // Example:
 // For this oneof:
@@ -54,10 +60,17 @@ namespace rust {
 //     FieldB(Mut<'msg, u32>) = 9,
 //     not_set = 0
 //   }
+//   pub enum SomeOneofOwned {
+//     FieldA(i32) = 7,
+//     FieldB(u32) = 9,
+//     not_set = 0
+//   }
 // }
 // impl SomeMsg {
 //   pub fn some_oneof() -> SomeOneof {...}
 //   pub fn some_oneof_mut() -> SomeOneofMut {...}
+//   pub fn take_some_oneof() -> SomeOneofOwned {...}
+//   pub fn clear_some_oneof() {...}
 
 // }
 //
@@ -84,6 +97,10 @@ std::string oneofMutEnumRsName(const OneofDescriptor& desc) {
   return ToCamelCase(desc.name()) + "Mut";
 }
 
+std::string oneofOwnedEnumRsName(const OneofDescriptor& desc) {
+  return ToCamelCase(desc.name()) + "Owned";
+}
+
 std::string oneofCaseEnumName(const OneofDescriptor& desc) {
   // Note: This is the name used for the cpp Case enum, we use it for both
   // the Rust Case enum as well as for the cpp case enum in the cpp thunk.
@@ -119,6 +136,32 @@ std::string RsTypeNameMut(const FieldDescriptor& desc) {
   return "Mut<'msg, " + type + ">";
 }
 
+std::string RsTypeNameOwned(const FieldDescriptor& desc) {
+  std::string type = RsTypeName(desc);
+  if (type.empty()) return "";
+  switch (desc.type()) {
+    case FieldDescriptor::TYPE_BYTES:
+      return "std::vec::Vec<u8>";
+    case FieldDescriptor::TYPE_STRING:
+      return "std::string::String";
+    default:
+      return type;
+  }
+}
+
+// Returns an expression converting the view returned by the field's getter
+// into the type named by `RsTypeNameOwned`.
+std::string RsOwnedFromView(const FieldDescriptor& desc) {
+  switch (desc.type()) {
+    case FieldDescriptor::TYPE_BYTES:
+      return ".to_vec()";
+    case FieldDescriptor::TYPE_STRING:
+      return ".to_cow_lossy().into_owned()";
+    default:
+      return "";
+  }
+}
+
 }  // namespace
 
 void GenerateOneofDefinition(Context<OneofDescriptor> oneof) {
@@ -156,6 +199,35 @@ void GenerateOneofDefinition(Context<OneofDescriptor> oneof) {
                        R"rs($name$($pb$::$type$) = $number$,
                 )rs");
           }
+        }},
+       {"owned_enum_name", oneofOwnedEnumRsName(desc)},
+       {"owned_fields",
+        [&] {
+          for (int i = 0; i < desc.field_count(); ++i) {
+            const auto& field = desc.field(i);
+            std::string rs_type = RsTypeNameOwned(*field);
+            if (rs_type.empty()) {
+              continue;
+            }
+            oneof.Emit({{"name", ToCamelCase(field->name())},
+                        {"type", rs_type},
+                        {"number", std::to_string(field->number())}},
+                       R"rs($name$($type$) = $number$,
+                )rs");
+          }
+        }},
+       {"to_owned_cases",
+        [&] {
+          for (int i = 0; i < desc.field_count(); ++i) {
+            const auto& field = desc.field(i);
+            if (RsTypeNameOwned(*field).empty()) {
+              continue;
+            }
+            oneof.Emit({{"name", ToCamelCase(field->name())},
+                        {"to_owned", RsOwnedFromView(*field)}},
+                       R"rs($view_enum_name$::$name$(v) => $owned_enum_name$::$name$(v$to_owned$),
+                )rs");
+          }
         }}},
       // TODO: Revisit if isize is the optimal repr for this enum.
       // TODO: not_set currently has phantom data just to avoid the
@@ -184,6 +256,28 @@ void GenerateOneofDefinition(Context<OneofDescriptor> oneof) {
         not_set(std::marker::PhantomData<&'msg ()>) = 0
       }
 
+      #[non_exhaustive]
+      #[derive(Debug, Clone, PartialEq)]
+      #[allow(dead_code)]
+      #[repr(isize)]
+      pub enum $owned_enum_name$ {
+        $owned_fields$
+
+        #[allow(non_camel_case_types)]
+        not_set = 0
+      }
+
+      /// Copies the set case into an owned value that no longer borrows from
+      /// the message.
+      impl<'msg> From<$view_enum_name$<'msg>> for $owned_enum_name$ {
+        fn from(view: $view_enum_name$<'msg>) -> Self {
+          match view {
+            $to_owned_cases$
+            $view_enum_name$::not_set(_) => $owned_enum_name$::not_set,
+          }
+        }
+      }
+
       )rs");
 
   // Note: This enum is used as the Thunk return type for getting which case is
@@ -268,7 +362,75 @@ void GenerateOneofAccessors(Context<OneofDescriptor> oneof) {
                $Msg$_::$mut_enum_name$::$case$(self.$rs_mut_getter$().try_into_mut().unwrap()), )rs");
           }
         }},
+       {"owned_enum_name", oneofOwnedEnumRsName(desc)},
+       {"take_cases",
+        [&] {
+          for (int i = 0; i < desc.field_count(); ++i) {
+            const auto& field = desc.field(i);
+            std::string rs_type = RsTypeNameOwned(*field);
+            if (rs_type.empty()) {
+              continue;
+            }
+            oneof.Emit(
+                {
+                    {"case", ToCamelCase(field->name())},
+                    {"rs_getter", field->name()},
+                    {"to_owned", RsOwnedFromView(*field)},
+                    {"clearer_thunk",
+                     Thunk(oneof.WithDesc(*field), "clear")},
+                },
+                R"rs($Msg$_::$case_enum_name$::$case$ => {
+                  let taken = $Msg$_::$owned_enum_name$::$case$(self.$rs_getter$()$to_owned$);
+                  unsafe { $clearer_thunk$(self.inner.msg) };
+                  taken
+                }
+                )rs");
+          }
+        }},
+       {"clear_body",
+        [&] {
+          if (oneof.is_cpp()) {
+            oneof.Emit({{"clear_thunk", Thunk(oneof, "clear")}},
+                       R"rs(
+                unsafe { $clear_thunk$(self.inner.msg) }
+              )rs");
+            return;
+          }
+          // upb has no clear for a whole oneof, so clear whichever field is
+          // set. Not every field declares its clearer in the message's extern
+          // block, so each arm declares the one it calls.
+          oneof.Emit(
+              {{"clear_cases",
+                [&] {
+                  for (int i = 0; i < desc.field_count(); ++i) {
+                    const auto& field = desc.field(i);
+                    oneof.Emit(
+                        {
+                            {"case", ToCamelCase(field->name())},
+                            {"clearer_thunk",
+                             Thunk(oneof.WithDesc(*field), "clear")},
+                        },
+                        R"rs(
+                      $Msg$_::$case_enum_name$::$case$ => {
+                        extern "C" {
+                          fn $clearer_thunk$(raw_msg: $pbi$::RawMessage);
+                        }
+                        unsafe { $clearer_thunk$(self.inner.msg) }
+                      }
+                    )rs");
+                  }
+                }}},
+              R"rs(
+                match unsafe { $case_thunk$(self.inner.msg) } {
+                  $clear_cases$
+                  $Msg$_::$case_enum_name$::not_set => {}
+                }
+              )rs");
+        }},
        {"case_thunk", Thunk(oneof, "case")}},
+      // Cases that cannot be spelled yet (e.g. messages and enums) fall
+      // through to `not_set`. `take_<oneof>()` leaves them set rather than
+      // dropping a value it cannot return.
       R"rs(
         pub fn r#$oneof_name$(&self) -> $Msg$_::$view_enum_name$ {
           match unsafe { $case_thunk$(self.inner.msg) } {
@@ -284,6 +446,22 @@ void GenerateOneofAccessors(Context<OneofDescriptor> oneof) {
           }
         }
 
+        /// Clears the oneof and returns the case that was set. String cases
+        /// are converted lossily, as by `ProtoStr::to_cow_lossy`.
+        ///
+        /// Message and enum cases have no owned value yet, so they are left
+        /// set and `not_set` is returned.
+        pub fn take_$oneof_name$(&mut self) -> $Msg$_::$owned_enum_name$ {
+          match unsafe { $case_thunk$(self.inner.msg) } {
+            $take_cases$
+            _ => $Msg$_::$owned_enum_name$::not_set
+          }
+        }
+
+        pub fn clear_$oneof_name$(&mut self) {
+          $clear_body$
+        }
+
       )rs");
 }
 
@@ -293,9 +471,19 @@ void GenerateOneofExternC(Context<OneofDescriptor> oneof) {
       {
           {"case_enum_rs_name", oneofCaseEnumName(desc)},
           {"case_thunk", Thunk(oneof, "case")},
+          {"clear_thunk_decl",
+           [&] {
+             // Only the C++ kernel has a thunk that clears the whole oneof.
+             if (oneof.is_cpp()) {
+               oneof.Emit({{"clear_thunk", Thunk(oneof, "clear")}}, R"rs(
+                 fn $clear_thunk$(raw_msg: $pbi$::RawMessage);
+               )rs");
+             }
+           }},
       },
       R"rs(
         fn $case_thunk$(raw_msg: $pbi$::RawMessage) -> $Msg$_::$case_enum_rs_name$;
+        $clear_thunk_decl$
       )rs");
 }
 
@@ -306,12 +494,14 @@ void GenerateOneofThunkCc(Context<OneofDescriptor> oneof) {
           {"oneof_name", desc.name()},
           {"case_enum_name", oneofCaseEnumName(desc)},
           {"case_thunk", Thunk(oneof, "case")},
+          {"clear_thunk", Thunk(oneof, "clear")},
           {"QualifiedMsg", cpp::QualifiedClassName(desc.containing_type())},
       },
       R"cc(
         $QualifiedMsg$::$case_enum_name$ $case_thunk$($QualifiedMsg$* msg) {
           return msg->$oneof_name$_case();
         }
+        void $clear_thunk$($QualifiedMsg$* msg) { msg->clear_$oneof_name$(); }
       )cc");
 }
 